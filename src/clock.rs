@@ -1,8 +1,63 @@
-use chrono::{FixedOffset, Local, Offset};
-use std::sync::LazyLock;
+use chrono_tz::Tz;
+use tracing::warn;
 
-static SYSTEM_OFFSET: LazyLock<FixedOffset> = LazyLock::new(|| Local::now().offset().fix());
+/**
+The timezone the cron schedules are evaluated in.
+It is the configured one, then the one from the `TZ` environment variable and finally UTC.
+*/
+pub(crate) fn resolve_timezone(configured: Option<&str>) -> Tz {
+    resolve_timezone_from(configured, std::env::var("TZ").ok().as_deref())
+}
+
+fn resolve_timezone_from(configured: Option<&str>, env: Option<&str>) -> Tz {
+    if let Some(timezone) = configured {
+        match timezone.parse() {
+            Ok(timezone) => return timezone,
+            Err(err) => warn!("Ignoring the invalid timezone {}: {}", timezone, err),
+        }
+    }
+
+    if let Some(timezone) = env.map(normalize_tz_env).filter(|timezone| !timezone.is_empty()) {
+        match timezone.parse() {
+            Ok(timezone) => return timezone,
+            Err(err) => warn!("Ignoring the invalid TZ environment variable {}: {}", timezone, err),
+        }
+    }
+
+    Tz::UTC
+}
+
+/**
+`TZ` may start with a colon or point into the zoneinfo database, e.g. `:/usr/share/zoneinfo/Europe/Berlin`.
+*/
+fn normalize_tz_env(value: &str) -> &str {
+    let value = value.trim_start_matches(':');
+    match value.split_once("zoneinfo/") {
+        Some((_, name)) => name,
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::resolve_timezone_from;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_configured_timezone_wins() {
+        assert_eq!(resolve_timezone_from(Some("Europe/Berlin"), Some("America/New_York")), Tz::Europe__Berlin);
+    }
+
+    #[test]
+    fn test_falls_back_to_tz_env() {
+        assert_eq!(resolve_timezone_from(None, Some("America/New_York")), Tz::America__New_York);
+        assert_eq!(resolve_timezone_from(None, Some(":/usr/share/zoneinfo/Europe/Berlin")), Tz::Europe__Berlin);
+    }
 
-pub(crate) fn get_system_timezone_offset() -> FixedOffset {
-    *SYSTEM_OFFSET
+    #[test]
+    fn test_falls_back_to_utc() {
+        assert_eq!(resolve_timezone_from(None, None), Tz::UTC);
+        assert_eq!(resolve_timezone_from(None, Some("")), Tz::UTC);
+        assert_eq!(resolve_timezone_from(None, Some("Not/AZone")), Tz::UTC);
+    }
 }
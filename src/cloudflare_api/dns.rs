@@ -0,0 +1,197 @@
+use crate::configuration::user::records::DnsType;
+use cloudflare::endpoints::dns::dns::DnsContent;
+use cloudflare::framework::endpoint::spec::EndpointSpec;
+use cloudflare::framework::endpoint::{Method, RequestBody, serialize_query};
+use cloudflare::framework::response::{ApiResult, ApiSuccess};
+use serde::{Deserialize, Serialize};
+
+/**
+The comment marker dyncloud uses to recognize the records it manages.
+*/
+pub(crate) const OWNERSHIP_MARKER: &str = "managed-by=dyncloud";
+
+/**
+The comment marker that names the dyncloud instance which created a record.
+*/
+const INSTANCE_MARKER: &str = "dyncloud-instance=";
+
+/*
+The DNS endpoints of the cloudflare crate don't know about record comments,
+so we bring our own versions of the ones we need.
+*/
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct DnsRecord {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) ttl: u32,
+    pub(crate) proxied: bool,
+    #[serde(flatten)]
+    pub(crate) content: DnsContent,
+    #[serde(default)]
+    pub(crate) comment: Option<String>,
+}
+
+impl DnsRecord {
+    pub(crate) fn is_owned_by(&self, instance: &str) -> bool {
+        is_owned_by(self.comment.as_deref(), instance)
+    }
+}
+
+impl ApiResult for DnsRecord {}
+
+/*
+A wrapper is needed because the orphan rule doesn't let us implement ApiResult for Vec<DnsRecord>.
+*/
+#[derive(Deserialize, Debug)]
+#[serde(transparent)]
+pub(crate) struct DnsRecords(pub(crate) Vec<DnsRecord>);
+
+impl ApiResult for DnsRecords {}
+
+pub(crate) fn is_owned(comment: Option<&str>) -> bool {
+    comment.is_some_and(|comment| comment.split_whitespace().any(|part| part == OWNERSHIP_MARKER))
+}
+
+/**
+The comment of the records we create. With an instance name, other instances in the same zone leave them alone.
+*/
+pub(crate) fn ownership_comment(instance: Option<&str>) -> String {
+    match instance {
+        Some(instance) => format!("{} {}{}", OWNERSHIP_MARKER, INSTANCE_MARKER, instance),
+        None => OWNERSHIP_MARKER.to_string(),
+    }
+}
+
+/**
+Whether the record was created by the given instance. Records of other instances or without a name don't match.
+*/
+pub(crate) fn is_owned_by(comment: Option<&str>, instance: &str) -> bool {
+    is_owned(comment)
+        && comment.is_some_and(|comment| {
+            comment.split_whitespace().any(|part| part.strip_prefix(INSTANCE_MARKER) == Some(instance))
+        })
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub(crate) struct ListDnsRecordsParams {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) record_type: Option<DnsType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) per_page: Option<u32>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ListDnsRecords<'a> {
+    pub(crate) zone_identifier: &'a str,
+    pub(crate) params: ListDnsRecordsParams,
+}
+
+impl EndpointSpec for ListDnsRecords<'_> {
+    type JsonResponse = DnsRecords;
+    type ResponseType = ApiSuccess<Self::JsonResponse>;
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn path(&self) -> String {
+        format!("zones/{}/dns_records", self.zone_identifier)
+    }
+
+    #[inline]
+    fn query(&self) -> Option<String> {
+        serialize_query(&self.params)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct DnsRecordParams<'a> {
+    pub(crate) ttl: u32,
+    pub(crate) proxied: bool,
+    pub(crate) name: &'a str,
+    #[serde(flatten)]
+    pub(crate) content: DnsContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) comment: Option<&'a str>,
+}
+
+#[derive(Debug)]
+pub(crate) struct CreateDnsRecord<'a> {
+    pub(crate) zone_identifier: &'a str,
+    pub(crate) params: DnsRecordParams<'a>,
+}
+
+impl EndpointSpec for CreateDnsRecord<'_> {
+    type JsonResponse = DnsRecord;
+    type ResponseType = ApiSuccess<Self::JsonResponse>;
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn path(&self) -> String {
+        format!("zones/{}/dns_records", self.zone_identifier)
+    }
+
+    #[inline]
+    fn body(&self) -> Option<RequestBody<'_>> {
+        serde_json::to_string(&self.params).ok().map(RequestBody::Json)
+    }
+}
+
+/**
+Overwrites the whole record, so the comment has to be sent again to keep it.
+*/
+#[derive(Debug)]
+pub(crate) struct UpdateDnsRecord<'a> {
+    pub(crate) zone_identifier: &'a str,
+    pub(crate) identifier: &'a str,
+    pub(crate) params: DnsRecordParams<'a>,
+}
+
+impl EndpointSpec for UpdateDnsRecord<'_> {
+    type JsonResponse = DnsRecord;
+    type ResponseType = ApiSuccess<Self::JsonResponse>;
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn path(&self) -> String {
+        format!("zones/{}/dns_records/{}", self.zone_identifier, self.identifier)
+    }
+
+    #[inline]
+    fn body(&self) -> Option<RequestBody<'_>> {
+        serde_json::to_string(&self.params).ok().map(RequestBody::Json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cloudflare_api::dns::{is_owned, is_owned_by, ownership_comment};
+
+    #[test]
+    fn test_is_owned() {
+        assert!(is_owned(Some("managed-by=dyncloud")));
+        assert!(is_owned(Some("home router managed-by=dyncloud")));
+        assert!(!is_owned(Some("managed-by=dyncloud-old")));
+        assert!(!is_owned(Some("")));
+        assert!(!is_owned(None));
+    }
+
+    #[test]
+    fn test_is_owned_by() {
+        assert!(is_owned_by(Some(&ownership_comment(Some("home"))), "home"));
+        assert!(is_owned_by(Some("dyncloud-instance=home managed-by=dyncloud"), "home"));
+        assert!(!is_owned_by(Some(&ownership_comment(Some("office"))), "home"));
+        assert!(!is_owned_by(Some(&ownership_comment(None)), "home"));
+        assert!(!is_owned_by(Some("dyncloud-instance=home"), "home"));
+        assert!(!is_owned_by(None, "home"));
+    }
+}
@@ -0,0 +1,61 @@
+pub(crate) mod dns;
+
+use crate::rate_limit::RateLimiter;
+use cloudflare::framework::Environment;
+use cloudflare::framework::auth::Credentials;
+use cloudflare::framework::client::ClientConfig;
+use cloudflare::framework::client::blocking_api::HttpApiClient;
+use cloudflare::framework::endpoint::spec::EndpointSpec;
+use cloudflare::framework::response::{ApiResponse, ResponseConverter};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/**
+A Cloudflare client that spaces out its requests to stay below the rate limit of the API.
+All records and zones of an API token share one client, so parallel syncs share the limit as well.
+*/
+pub(crate) struct CloudflareClient {
+    http_client: HttpApiClient,
+    rate_limiter: RateLimiter,
+}
+
+impl CloudflareClient {
+    pub(crate) fn new(http_client: HttpApiClient, rate_limiter: RateLimiter) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+        }
+    }
+
+    pub(crate) fn request<Endpoint>(&self, endpoint: &Endpoint) -> ApiResponse<Endpoint::ResponseType>
+    where
+        Endpoint: EndpointSpec + Send + Sync,
+        Endpoint::ResponseType: ResponseConverter<Endpoint::JsonResponse>,
+    {
+        self.rate_limiter.acquire();
+        self.http_client.request(endpoint)
+    }
+}
+
+pub(crate) fn build_cloudflare_client(auth_token: String) -> HttpApiClient {
+    build_cloudflare_client_with_token_and_ip(auth_token, None)
+}
+
+pub(crate) fn build_cloudflare_client_with_token_and_ip(auth_token: String, ip: Option<IpAddr>) -> HttpApiClient {
+    let client_config = ClientConfig {
+        http_timeout: Duration::from_secs(30),
+        default_headers: http::HeaderMap::default(),
+        resolve_ip: ip,
+    };
+
+    match HttpApiClient::new(
+        Credentials::UserAuthToken {
+            token: auth_token,
+        },
+        client_config,
+        Environment::Production,
+    ) {
+        Ok(client) => client,
+        Err(e) => panic!("Error creating Cloudflare client: {e:?}"),
+    }
+}
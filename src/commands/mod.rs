@@ -1 +1,2 @@
 pub(crate) mod cloudflare;
+pub(crate) mod sync;
@@ -0,0 +1,65 @@
+use crate::dns::plan::{PlanEntry, PlannedAction, PlannedChange, format_dns_content};
+use crate::dns::report::SyncReport;
+use comfy_table::Table;
+use comfy_table::presets::UTF8_FULL;
+
+pub(crate) fn print_plan(entries: &[PlanEntry]) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Record", "Type", "Action", "Current", "Desired", "Error"]);
+    for entry in entries {
+        let row = match entry {
+            PlanEntry::Change(change) => change_row(change, change.action.to_string(), String::new()),
+            PlanEntry::Blocked {
+                change,
+                reason,
+            } => change_row(change, format!("{} (blocked)", change.action), reason.to_string()),
+            PlanEntry::Failed {
+                name,
+                dns_type,
+                error,
+            } => vec![
+                name.clone(),
+                dns_type.to_string(),
+                "failed".to_string(),
+                String::new(),
+                String::new(),
+                error.to_string(),
+            ],
+        };
+        table.add_row(row);
+    }
+
+    println!("{}", table);
+}
+
+fn change_row(change: &PlannedChange, action: String, error: String) -> Vec<String> {
+    vec![
+        change.name.clone(),
+        change.dns_type.to_string(),
+        action,
+        change.action.current().map(|current| format_dns_content(&current.content)).unwrap_or_default(),
+        match change.action {
+            PlannedAction::Delete {
+                ..
+            } => String::new(),
+            _ => format_dns_content(&change.desired),
+        },
+        error,
+    ]
+}
+
+pub(crate) fn print_report(report: &SyncReport) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Record", "Type", "Outcome", "Error"]);
+    for record in &report.records {
+        let (outcome, error) = match &record.result {
+            Ok(outcome) => (outcome.to_string(), String::new()),
+            Err(err) => ("failed".to_string(), err.to_string()),
+        };
+        table.add_row(vec![record.name.clone(), record.dns_type.to_string(), outcome, error]);
+    }
+
+    println!("{}", table);
+}
@@ -1,18 +1,62 @@
 use crate::configuration::user::error::ConfigError;
+use crate::configuration::user::prune::PruneConfig;
 use crate::configuration::user::records::RecordsGroup;
+use crate::configuration::user::retry::RetryConfig;
+use crate::configuration::user::schedule::Schedule;
+use crate::configuration::user::stabilization::StabilizationConfig;
 use crate::configuration::validation::cron::validate_cron_expression;
+use crate::configuration::validation::schedule::{validate_config_schedule, validate_interval};
+use crate::configuration::validation::timezone::validate_timezone;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::time::Duration;
 use validator::Validate;
 
 #[derive(Debug, Deserialize, Validate)]
 #[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_config_schedule"))]
 pub(crate) struct Config {
+    #[serde(default)]
     #[validate(custom(function = "validate_cron_expression"))]
-    pub(crate) cron: String,
+    pub(crate) cron: Option<String>,
+    #[serde(default, with = "humantime_serde")]
+    #[validate(custom(function = "validate_interval"))]
+    pub(crate) interval: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) splay: Option<Duration>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_timezone"))]
+    pub(crate) timezone: Option<String>,
+    #[serde(default = "default_sync_on_start")]
+    pub(crate) sync_on_start: bool,
+    /**
+    How many records are synced at the same time.
+    */
+    #[serde(default = "default_concurrency")]
+    #[validate(range(min = 1, max = 32))]
+    pub(crate) concurrency: usize,
     #[serde(rename = "domains")]
     #[validate(nested)]
     pub(crate) records: Vec<RecordsGroup>,
+    #[serde(default)]
+    pub(crate) state_file: Option<PathBuf>,
+    #[serde(default)]
+    #[validate(nested)]
+    pub(crate) retry: RetryConfig,
+    #[serde(default)]
+    #[validate(nested)]
+    pub(crate) prune: PruneConfig,
+    #[serde(default)]
+    #[validate(nested)]
+    pub(crate) stabilization: StabilizationConfig,
+}
+
+fn default_sync_on_start() -> bool {
+    true
+}
+
+fn default_concurrency() -> usize {
+    4
 }
 
 impl Config {
@@ -28,6 +72,19 @@ impl Config {
         Ok(config)
     }
 
+    /**
+    The global schedule. Validation makes sure that exactly one of `cron` and `interval` is set.
+    */
+    pub(crate) fn schedule(&self) -> Schedule {
+        match self.interval {
+            Some(every) => Schedule::Interval {
+                every,
+                splay: self.splay.unwrap_or_default(),
+            },
+            None => Schedule::Cron(self.cron.clone().unwrap_or_default()),
+        }
+    }
+
     pub(crate) fn get_total_number_of_records(&self) -> u32 {
         self.records
             .iter()
@@ -54,8 +111,12 @@ mod tests {
                     name: "test.example.com".to_string(),
                     ttl: 300,
                     dns_type: dns_types,
+                    cron: None,
+                    force_update_after: None,
                 },
                 proxied: false,
+                on_duplicate: Default::default(),
+                require_ownership: false,
             }
         }
 
@@ -66,6 +127,8 @@ mod tests {
                 },
                 cloudflare: cloudflare_records,
                 resolver: Default::default(),
+                cron: None,
+                force_update_after: None,
             }
         }
     }
@@ -90,8 +153,17 @@ mod tests {
     #[test]
     fn test_get_total_number_of_records_empty() {
         let config = Config {
-            cron: "0 0 * * *".to_string(),
+            cron: Some("0 0 * * *".to_string()),
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert_eq!(config.get_total_number_of_records(), 0);
@@ -100,11 +172,20 @@ mod tests {
     #[test]
     fn test_get_total_number_of_records_single_record() {
         let config = Config {
-            cron: "0 0 * * *".to_string(),
+            cron: Some("0 0 * * *".to_string()),
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![Config::create_test_records_group(vec![Config::create_test_cloudflare_record(vec![
                 DnsType::A,
                 DnsType::Aaaa,
             ])])],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert_eq!(config.get_total_number_of_records(), 2);
@@ -113,11 +194,20 @@ mod tests {
     #[test]
     fn test_get_total_number_of_records_multiple_groups() {
         let config = Config {
-            cron: "0 0 * * *".to_string(),
+            cron: Some("0 0 * * *".to_string()),
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![
                 Config::create_test_records_group(vec![Config::create_test_cloudflare_record(vec![DnsType::A])]),
                 Config::create_test_records_group(vec![Config::create_test_cloudflare_record(vec![DnsType::Aaaa])]),
             ],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert_eq!(config.get_total_number_of_records(), 2);
@@ -126,8 +216,17 @@ mod tests {
     #[test]
     fn test_get_total_number_of_records_empty_dns_types() {
         let config = Config {
-            cron: "0 0 * * *".to_string(),
+            cron: Some("0 0 * * *".to_string()),
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![Config::create_test_records_group(vec![Config::create_test_cloudflare_record(vec![])])],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert_eq!(config.get_total_number_of_records(), 0);
@@ -136,13 +235,19 @@ mod tests {
     #[test]
     fn test_valid_config() {
         let config = Config {
-            cron: "* * * * *".to_string(),
+            cron: Some("* * * * *".to_string()),
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![
                 RecordsGroup {
                     providers: Providers {
                         cloudflare: Some(Cloudflare {
                             auth_token: "My auth token".to_string(),
                             zone_id: "My Zone id".to_string(),
+                            requests_per_second: 4.0,
                         }),
                     },
                     cloudflare: vec![CloudflareRecord {
@@ -150,10 +255,16 @@ mod tests {
                             name: "test.example.test".to_string(),
                             ttl: 60,
                             dns_type: vec![DnsType::A, DnsType::Aaaa],
+                            cron: None,
+                            force_update_after: None,
                         },
                         proxied: false,
+                        on_duplicate: Default::default(),
+                        require_ownership: false,
                     }],
                     resolver: Resolver::Ipfiy,
+                    cron: None,
+                    force_update_after: None,
                 },
                 RecordsGroup {
                     providers: Providers {
@@ -161,8 +272,14 @@ mod tests {
                     },
                     cloudflare: vec![],
                     resolver: Resolver::Ipfiy,
+                    cron: None,
+                    force_update_after: None,
                 },
             ],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert!(config.validate().is_ok());
@@ -171,7 +288,12 @@ mod tests {
     #[test]
     fn test_invalid_config() {
         let config = Config {
-            cron: "".to_string(), // Invalid cron
+            cron: Some("".to_string()), // Invalid cron
+            interval: None,
+            splay: None,
+            timezone: None,
+            sync_on_start: true,
+            concurrency: 4,
             records: vec![RecordsGroup {
                 providers: Providers {
                     cloudflare: None, // Cloudflare Record given but no provider
@@ -181,11 +303,21 @@ mod tests {
                         name: "".to_string(), // Empty record name
                         ttl: 60,
                         dns_type: vec![], // No DnsType specified
+                        cron: None,
+                        force_update_after: None,
                     },
                     proxied: false,
+                    on_duplicate: Default::default(),
+                    require_ownership: false,
                 }],
                 resolver: Resolver::Ipfiy,
+                cron: None,
+                force_update_after: None,
             }],
+            state_file: None,
+            retry: Default::default(),
+            prune: Default::default(),
+            stabilization: Default::default(),
         };
 
         assert!(config.validate().is_err());
@@ -1,5 +1,9 @@
 pub(crate) mod config;
 pub(crate) mod error;
 pub(crate) mod providers;
+pub(crate) mod prune;
 pub(crate) mod records;
 pub(crate) mod resolver;
+pub(crate) mod retry;
+pub(crate) mod schedule;
+pub(crate) mod stabilization;
@@ -9,11 +9,21 @@ pub(crate) struct Providers {
     pub(crate) cloudflare: Option<Cloudflare>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate)]
 #[cfg_attr(test, derive(serde::Serialize))]
 pub(crate) struct Cloudflare {
     #[validate(length(min = 1))]
     pub(crate) auth_token: String,
     #[validate(length(min = 1))]
     pub(crate) zone_id: String,
+    /**
+    Cloudflare allows 1200 requests per 5 minutes for each user, which is 4 per second.
+    */
+    #[serde(default = "default_requests_per_second")]
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub(crate) requests_per_second: f64,
+}
+
+fn default_requests_per_second() -> f64 {
+    4.0
 }
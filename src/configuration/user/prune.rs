@@ -0,0 +1,75 @@
+use crate::configuration::validation::prune::validate_prune_config;
+use serde::Deserialize;
+use validator::Validate;
+
+/**
+Deleting records is opt-in. Only records this instance created are ever pruned,
+so several instances can share a zone without deleting each other's records.
+*/
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_prune_config"))]
+pub(crate) struct PruneConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /**
+    Written into the comment of every record this instance creates. Required for pruning.
+    */
+    #[serde(default)]
+    pub(crate) instance: Option<String>,
+    /**
+    Safety cap: when a run would delete more records than this, nothing is deleted at all.
+    */
+    #[serde(default = "default_max_deletions")]
+    #[validate(range(min = 1))]
+    pub(crate) max_deletions: usize,
+}
+
+impl Default for PruneConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            instance: None,
+            max_deletions: default_max_deletions(),
+        }
+    }
+}
+
+fn default_max_deletions() -> usize {
+    10
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::prune::PruneConfig;
+    use validator::Validate;
+
+    #[test]
+    fn test_prune_is_disabled_by_default() {
+        let config: PruneConfig = toml::from_str("").unwrap();
+
+        assert!(!config.enabled);
+        assert_eq!(config.max_deletions, 10);
+    }
+
+    #[test]
+    fn test_max_deletions_must_be_positive() {
+        let config = PruneConfig {
+            enabled: true,
+            instance: Some("home".to_string()),
+            max_deletions: 0,
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_pruning_needs_an_instance() {
+        let config: PruneConfig = toml::from_str("enabled = true").unwrap();
+        assert!(config.validate().is_err());
+
+        let config: PruneConfig = toml::from_str("enabled = true\ninstance = \"home\"").unwrap();
+        assert!(config.validate().is_ok());
+    }
+}
@@ -1,8 +1,11 @@
 use crate::configuration::user::providers::Providers;
 use crate::configuration::user::resolver::Resolver;
+use crate::configuration::user::schedule::Schedule;
+use crate::configuration::validation::cron::validate_cron_expression;
 use crate::configuration::validation::records_group::validate_record_groups_schema;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::time::Duration;
 use validator::Validate;
 
 #[derive(Debug, Deserialize, Validate)]
@@ -16,7 +19,19 @@ pub(crate) struct RecordsGroup {
     #[validate(nested)]
     pub(crate) cloudflare: Vec<CloudflareRecord>,
     #[serde(default)]
+    #[validate(nested)]
     pub(crate) resolver: Resolver,
+    /**
+    Overrides the global cron for all records of this group.
+    */
+    #[serde(default)]
+    #[validate(custom(function = "validate_cron_expression"))]
+    pub(crate) cron: Option<String>,
+    /**
+    Writes the records of this group after this long, even if the IP did not change.
+    */
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) force_update_after: Option<Duration>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -28,6 +43,29 @@ pub(crate) struct BasicRecord {
     #[serde(rename = "type")]
     #[validate(length(min = 1))]
     pub(crate) dns_type: Vec<DnsType>,
+    /**
+    Overrides the cron of the group and the global one for this record.
+    */
+    #[serde(default)]
+    #[validate(custom(function = "validate_cron_expression"))]
+    pub(crate) cron: Option<String>,
+    /**
+    Writes the record after this long, even if the IP did not change. Overrides the one of the group.
+    */
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) force_update_after: Option<Duration>,
+}
+
+impl BasicRecord {
+    /**
+    The cron of the record, falling back to the one of its group and then to the global schedule.
+    */
+    pub(crate) fn effective_schedule(&self, group_cron: Option<&str>, global: &Schedule) -> Schedule {
+        match self.cron.as_deref().or(group_cron) {
+            Some(cron) => Schedule::Cron(cron.to_string()),
+            None => global.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Deserialize, Serialize)]
@@ -54,6 +92,38 @@ pub(crate) struct CloudflareRecord {
     pub(crate) basic_record: BasicRecord,
     #[serde(default = "default_proxied")]
     pub(crate) proxied: bool,
+    #[serde(default)]
+    pub(crate) on_duplicate: OnDuplicate,
+    /**
+    Only touch existing records that carry the dyncloud ownership marker.
+    */
+    #[serde(default)]
+    pub(crate) require_ownership: bool,
+}
+
+/**
+What to do when more than one record of the same name and type exists.
+*/
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum OnDuplicate {
+    /**
+    Don't touch any of the records and report the record as failed.
+    */
+    #[default]
+    Fail,
+    /**
+    Update all matching records to the current address.
+    */
+    UpdateAll,
+    /**
+    Update the first matching record and delete all others.
+    */
+    KeepFirst,
+    /**
+    Only update the records carrying the dyncloud ownership marker in their comment.
+    */
+    UpdateOwned,
 }
 
 fn default_proxied() -> bool {
@@ -61,8 +131,11 @@ fn default_proxied() -> bool {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use crate::configuration::user::records::{BasicRecord, DnsType};
+    use crate::configuration::user::schedule::Schedule;
+    use std::time::Duration;
     use validator::Validate;
 
     #[test]
@@ -71,6 +144,8 @@ mod tests {
             name: "My Record".to_string(),
             ttl: 120,
             dns_type: vec![DnsType::A],
+            cron: None,
+            force_update_after: None,
         };
 
         assert!(record.validate().is_ok());
@@ -82,10 +157,52 @@ mod tests {
             name: "".to_string(),
             ttl: 0,
             dns_type: vec![],
+            cron: None,
+            force_update_after: None,
         };
 
         let rs = record.validate();
         assert!(rs.is_err());
         assert_eq!(rs.err().unwrap().0.len(), 2);
     }
+
+    #[test]
+    fn test_invalid_record_cron() {
+        let record = BasicRecord {
+            name: "My Record".to_string(),
+            ttl: 120,
+            dns_type: vec![DnsType::A],
+            cron: Some("Not a valid cron expression".to_string()),
+            force_update_after: None,
+        };
+
+        assert!(record.validate().is_err());
+    }
+
+    #[test]
+    fn test_effective_schedule() {
+        let global = Schedule::Interval {
+            every: Duration::from_secs(300),
+            splay: Duration::ZERO,
+        };
+        let mut record = BasicRecord {
+            name: "My Record".to_string(),
+            ttl: 120,
+            dns_type: vec![DnsType::A],
+            cron: None,
+            force_update_after: None,
+        };
+
+        assert_eq!(record.effective_schedule(None, &global), global);
+        assert_eq!(
+            record.effective_schedule(Some("0 */5 * * * *"), &global),
+            Schedule::Cron("0 */5 * * * *".to_string())
+        );
+
+        record.cron = Some("0 * * * * *".to_string());
+        assert_eq!(
+            record.effective_schedule(Some("0 */5 * * * *"), &global),
+            Schedule::Cron("0 * * * * *".to_string())
+        );
+    }
 }
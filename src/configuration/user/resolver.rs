@@ -1,8 +1,394 @@
+use crate::configuration::validation::resolver::{
+    validate_consensus_resolver, validate_fallback_resolver, validate_headers, validate_http_resolver,
+    validate_interface_name,
+};
+use regex::Regex;
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use validator::{Validate, ValidationErrors};
 
 #[derive(Debug, Default, Deserialize)]
 #[cfg_attr(test, derive(serde::Serialize))]
 pub(crate) enum Resolver {
     #[default]
     Ipfiy,
+    Icanhazip,
+    IfconfigCo,
+    IfconfigMe,
+    Ipinfo,
+    AwsCheckip,
+    CloudflareTrace,
+    /**
+    A lookup service of your own.
+    */
+    Http(HttpResolverConfig),
+    /**
+    Tries several resolvers in order until one of them answers.
+    */
+    Fallback(FallbackResolverConfig),
+    /**
+    Asks several resolvers and only accepts an address enough of them agree on.
+    */
+    Consensus(ConsensusResolverConfig),
+    /**
+    Reads the address of a local network interface (Linux only).
+    */
+    Interface {
+        name: String,
+    },
+}
+
+impl Resolver {
+    pub(crate) fn supports_ipv4(&self) -> bool {
+        match self {
+            Resolver::Http(config) => config.ipv4_url.is_some(),
+            Resolver::Fallback(config) => config.resolvers.iter().any(Resolver::supports_ipv4),
+            Resolver::Consensus(config) => {
+                config.resolvers.iter().filter(|r| r.supports_ipv4()).count() >= config.quorum
+            }
+            _ => true,
+        }
+    }
+
+    pub(crate) fn supports_ipv6(&self) -> bool {
+        match self {
+            Resolver::AwsCheckip => false,
+            Resolver::Http(config) => config.ipv6_url.is_some(),
+            Resolver::Fallback(config) => config.resolvers.iter().any(Resolver::supports_ipv6),
+            Resolver::Consensus(config) => {
+                config.resolvers.iter().filter(|r| r.supports_ipv6()).count() >= config.quorum
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Display for Resolver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resolver::Ipfiy => write!(f, "ipify"),
+            Resolver::Icanhazip => write!(f, "icanhazip"),
+            Resolver::IfconfigCo => write!(f, "ifconfig.co"),
+            Resolver::IfconfigMe => write!(f, "ifconfig.me"),
+            Resolver::Ipinfo => write!(f, "ipinfo"),
+            Resolver::AwsCheckip => write!(f, "AWS checkip"),
+            Resolver::CloudflareTrace => write!(f, "Cloudflare trace"),
+            Resolver::Http(config) => {
+                write!(f, "HTTP ({})", config.ipv4_url.as_ref().or(config.ipv6_url.as_ref()).map_or("", |url| url))
+            }
+            Resolver::Fallback(_) => write!(f, "fallback"),
+            Resolver::Consensus(_) => write!(f, "consensus"),
+            Resolver::Interface {
+                name,
+            } => write!(f, "interface {}", name),
+        }
+    }
+}
+
+impl Validate for Resolver {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            Resolver::Http(config) => config.validate(),
+            Resolver::Fallback(config) => config.validate(),
+            Resolver::Consensus(config) => config.validate(),
+            Resolver::Interface {
+                name,
+            } => {
+                let mut errors = ValidationErrors::new();
+                if let Err(err) = validate_interface_name(name) {
+                    errors.add("name", err);
+                }
+                match errors.is_empty() {
+                    true => Ok(()),
+                    false => Err(errors),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/**
+An ordered list of resolvers. The first one that returns a valid address wins.
+Resolvers that fail `max_failures` times in a row are skipped for the `cooldown`.
+*/
+#[derive(Debug, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_fallback_resolver"))]
+pub(crate) struct FallbackResolverConfig {
+    #[validate(nested)]
+    pub(crate) resolvers: Vec<Resolver>,
+    #[serde(default = "default_max_failures")]
+    #[validate(range(min = 1))]
+    pub(crate) max_failures: u32,
+    #[serde(default = "default_cooldown", with = "humantime_serde")]
+    pub(crate) cooldown: Duration,
+}
+
+/**
+Asks all resolvers and only accepts an address that at least `quorum` of them return,
+so a single compromised or broken service can't point the records somewhere else.
+If more than one address reaches the quorum, the resolvers disagree and no address is accepted.
+*/
+#[derive(Debug, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_consensus_resolver"))]
+pub(crate) struct ConsensusResolverConfig {
+    #[validate(nested)]
+    pub(crate) resolvers: Vec<Resolver>,
+    #[validate(range(min = 1))]
+    pub(crate) quorum: usize,
+}
+
+fn default_max_failures() -> u32 {
+    3
+}
+
+fn default_cooldown() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_http_resolver"))]
+pub(crate) struct HttpResolverConfig {
+    #[serde(default)]
+    #[validate(url)]
+    pub(crate) ipv4_url: Option<String>,
+    #[serde(default)]
+    #[validate(url)]
+    pub(crate) ipv6_url: Option<String>,
+    /**
+    Sent with every request, e.g. to authenticate against the service.
+    */
+    #[serde(default)]
+    #[validate(custom(function = "validate_headers"))]
+    pub(crate) headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) extract: Extraction,
+}
+
+/**
+How the address is taken out of the response body.
+*/
+#[derive(Debug, Default, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Extraction {
+    /**
+    The whole body, without surrounding whitespace.
+    */
+    #[default]
+    Body,
+    /**
+    The first capture group of a regular expression.
+    */
+    Regex(CapturePattern),
+    /**
+    A string in a JSON document, e.g. `/ip` or `/data/address`.
+    */
+    JsonPointer(String),
+}
+
+/**
+A regular expression with at least one capture group. It is compiled while the config is parsed.
+*/
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(try_from = "String")]
+#[cfg_attr(test, serde(into = "String"))]
+pub(crate) struct CapturePattern(pub(crate) Regex);
+
+impl TryFrom<String> for CapturePattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        let regex = Regex::new(&pattern).map_err(|err| err.to_string())?;
+        if regex.captures_len() < 2 {
+            return Err(format!("The regex {:?} needs a capture group for the address", pattern));
+        }
+
+        Ok(Self(regex))
+    }
+}
+
+impl From<CapturePattern> for String {
+    fn from(pattern: CapturePattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::resolver::{Extraction, Resolver};
+    use std::time::Duration;
+    use validator::Validate;
+
+    #[derive(serde::Deserialize)]
+    struct Group {
+        resolver: Resolver,
+    }
+
+    #[test]
+    fn test_parse_builtin_resolver() {
+        let group: Group = toml::from_str("resolver = \"CloudflareTrace\"").unwrap();
+
+        assert!(matches!(group.resolver, Resolver::CloudflareTrace));
+    }
+
+    #[test]
+    fn test_parse_http_resolver() {
+        let group: Group = toml::from_str(
+            r#"
+            [resolver.Http]
+            ipv4_url = "https://ip.corp.internal/v4"
+            headers = { Authorization = "Bearer token" }
+            extract = { regex = "address: (\\S+)" }
+            "#,
+        )
+        .unwrap();
+
+        let Resolver::Http(config) = &group.resolver else {
+            panic!("Expected an HTTP resolver");
+        };
+        assert_eq!(config.headers["Authorization"], "Bearer token");
+        assert!(matches!(&config.extract, Extraction::Regex(pattern) if pattern.0.as_str() == "address: (\\S+)"));
+        assert!(group.resolver.supports_ipv4());
+        assert!(!group.resolver.supports_ipv6());
+        assert!(group.resolver.validate().is_ok());
+    }
+
+    #[test]
+    fn test_regex_needs_a_capture_group() {
+        let group = toml::from_str::<Group>(
+            r#"
+            [resolver.Http]
+            ipv4_url = "https://ip.corp.internal/v4"
+            extract = { regex = "\\d+\\.\\d+\\.\\d+\\.\\d+" }
+            "#,
+        );
+
+        assert!(group.is_err());
+    }
+
+    #[test]
+    fn test_invalid_http_resolver() {
+        let group: Group = toml::from_str(
+            r#"
+            [resolver.Http]
+            ipv4_url = "not a url"
+            headers = { "Bad Header" = "value" }
+            "#,
+        )
+        .unwrap();
+
+        let rs = group.resolver.validate();
+        assert!(rs.is_err());
+        assert_eq!(rs.err().unwrap().errors().len(), 2);
+    }
+
+    #[test]
+    fn test_http_resolver_needs_a_url() {
+        let group: Group = toml::from_str(
+            r#"
+            [resolver.Http]
+            extract = { json_pointer = "/ip" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(group.resolver.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_fallback_resolver() {
+        let group: Group = toml::from_str(
+            r#"
+            [resolver.Fallback]
+            resolvers = ["Ipfiy", "AwsCheckip", { Http = { ipv6_url = "https://ip.corp.internal/v6" } }]
+            cooldown = "10m"
+            "#,
+        )
+        .unwrap();
+
+        let Resolver::Fallback(config) = &group.resolver else {
+            panic!("Expected a fallback resolver");
+        };
+        assert_eq!(config.resolvers.len(), 3);
+        assert_eq!(config.resolvers[2].to_string(), "HTTP (https://ip.corp.internal/v6)");
+        assert_eq!(config.max_failures, 3);
+        assert_eq!(config.cooldown, Duration::from_secs(600));
+        assert!(group.resolver.supports_ipv6());
+        assert!(group.resolver.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_fallback_resolver() {
+        let empty: Group = toml::from_str("[resolver.Fallback]\nresolvers = []").unwrap();
+        let nested: Group =
+            toml::from_str("[resolver.Fallback]\nresolvers = [{ Fallback = { resolvers = [\"Ipfiy\"] } }]").unwrap();
+        let invalid_member: Group = toml::from_str("[resolver.Fallback]\nresolvers = [{ Http = {} }]").unwrap();
+
+        assert!(empty.resolver.validate().is_err());
+        assert!(nested.resolver.validate().is_err());
+        assert!(invalid_member.resolver.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_consensus_resolver() {
+        let group: Group = toml::from_str(
+            r#"
+            [resolver.Consensus]
+            resolvers = ["Ipfiy", "Icanhazip", "AwsCheckip"]
+            quorum = 3
+            "#,
+        )
+        .unwrap();
+
+        assert!(group.resolver.validate().is_ok());
+        assert!(group.resolver.supports_ipv4());
+        // Only two of the resolvers can look up IPv6 addresses.
+        assert!(!group.resolver.supports_ipv6());
+    }
+
+    #[test]
+    fn test_invalid_consensus_resolver() {
+        let too_few: Group = toml::from_str("[resolver.Consensus]\nresolvers = [\"Ipfiy\"]\nquorum = 2").unwrap();
+        let nested: Group = toml::from_str(
+            "[resolver.Consensus]\nresolvers = [\"Ipfiy\", { Fallback = { resolvers = [\"Ipinfo\"] } }]\nquorum = 2",
+        )
+        .unwrap();
+
+        let no_quorum: Group =
+            toml::from_str("[resolver.Consensus]\nresolvers = [\"Ipfiy\", \"Icanhazip\"]\nquorum = 0").unwrap();
+
+        assert!(too_few.resolver.validate().is_err());
+        assert!(no_quorum.resolver.validate().is_err());
+        assert!(nested.resolver.validate().is_err());
+    }
+
+    #[test]
+    fn test_consensus_quorum_can_be_half_of_the_resolvers() {
+        let group: Group = toml::from_str(
+            "[resolver.Consensus]\nresolvers = [\"Ipfiy\", \"Icanhazip\", \"IfconfigCo\", \"Ipinfo\"]\nquorum = 2",
+        )
+        .unwrap();
+
+        assert!(group.resolver.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_interface_resolver() {
+        let group: Group = toml::from_str("resolver = { Interface = { name = \"ppp0\" } }").unwrap();
+
+        assert!(matches!(&group.resolver, Resolver::Interface { name } if name == "ppp0"));
+        assert_eq!(group.resolver.to_string(), "interface ppp0");
+        assert!(group.resolver.validate().is_ok());
+
+        let empty: Group = toml::from_str("resolver = { Interface = { name = \"\" } }").unwrap();
+        assert!(empty.resolver.validate().is_err());
+    }
 }
@@ -0,0 +1,84 @@
+use crate::configuration::validation::retry::validate_retry_config;
+use serde::Deserialize;
+use std::time::Duration;
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[validate(schema(function = "validate_retry_config"))]
+pub(crate) struct RetryConfig {
+    #[serde(default = "default_max_attempts")]
+    #[validate(range(min = 1, max = 10))]
+    pub(crate) max_attempts: u32,
+    #[serde(default = "default_base_delay", with = "humantime_serde")]
+    pub(crate) base_delay: Duration,
+    #[serde(default = "default_max_delay", with = "humantime_serde")]
+    pub(crate) max_delay: Duration,
+    #[serde(default = "default_jitter")]
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub(crate) jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_delay: default_base_delay(),
+            max_delay: default_max_delay(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_base_delay() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_max_delay() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::retry::RetryConfig;
+    use std::time::Duration;
+    use validator::Validate;
+
+    #[test]
+    fn test_default_retry_config_is_valid() {
+        assert!(RetryConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_retry_config() {
+        let config = RetryConfig {
+            max_attempts: 0,
+            jitter: 1.5,
+            ..Default::default()
+        };
+
+        let rs = config.validate();
+        assert!(rs.is_err());
+        assert_eq!(rs.err().unwrap().0.len(), 2);
+    }
+
+    #[test]
+    fn test_base_delay_cant_exceed_max_delay() {
+        let config = RetryConfig {
+            base_delay: Duration::from_secs(60),
+            max_delay: Duration::from_secs(30),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use humantime_serde::re::humantime::format_duration;
+use std::fmt::Display;
+use std::time::Duration;
+
+/**
+When the records are checked, either by a cron pattern or every fixed interval.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Schedule {
+    Cron(String),
+    /**
+    Each run is delayed by a random duration of up to `splay`, so many instances don't hit the APIs at once.
+    */
+    Interval {
+        every: Duration,
+        splay: Duration,
+    },
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Cron(cron) => write!(f, "{}", cron),
+            Schedule::Interval {
+                every,
+                splay,
+            } if splay.is_zero() => write!(f, "every {}", format_duration(*every)),
+            Schedule::Interval {
+                every,
+                splay,
+            } => write!(f, "every {} (+ up to {})", format_duration(*every), format_duration(*splay)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::user::schedule::Schedule;
+    use std::time::Duration;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Schedule::Cron("0 * * * * *".to_string()).to_string(), "0 * * * * *");
+        assert_eq!(
+            Schedule::Interval {
+                every: Duration::from_secs(300),
+                splay: Duration::ZERO,
+            }
+            .to_string(),
+            "every 5m"
+        );
+        assert_eq!(
+            Schedule::Interval {
+                every: Duration::from_secs(300),
+                splay: Duration::from_secs(30),
+            }
+            .to_string(),
+            "every 5m (+ up to 30s)"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use std::time::Duration;
+use validator::Validate;
+
+/**
+Delays syncing a new public IP until it stopped changing, e.g. on failover links that flip between two addresses.
+Without any limit every new address is synced right away.
+*/
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+pub(crate) struct StabilizationConfig {
+    /**
+    A new address has to be seen on this many consecutive checks.
+    */
+    #[serde(default)]
+    #[validate(range(min = 1))]
+    pub(crate) consecutive_checks: Option<u32>,
+    /**
+    Or it has to be seen for at least this long.
+    */
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) min_duration: Option<Duration>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::stabilization::StabilizationConfig;
+    use std::time::Duration;
+    use validator::Validate;
+
+    #[test]
+    fn test_stabilization_is_disabled_by_default() {
+        let config: StabilizationConfig = toml::from_str("").unwrap();
+
+        assert_eq!(config.consecutive_checks, None);
+        assert_eq!(config.min_duration, None);
+    }
+
+    #[test]
+    fn test_parse_stabilization() {
+        let config: StabilizationConfig = toml::from_str("consecutive_checks = 3\nmin_duration = \"5m\"").unwrap();
+
+        assert_eq!(config.consecutive_checks, Some(3));
+        assert_eq!(config.min_duration, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_consecutive_checks_must_be_positive() {
+        let config = StabilizationConfig {
+            consecutive_checks: Some(0),
+            min_duration: None,
+        };
+
+        assert!(config.validate().is_err());
+    }
+}
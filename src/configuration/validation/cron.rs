@@ -1,4 +1,4 @@
-use job_scheduler_ng::Cron;
+use croner::Cron;
 use std::str::FromStr;
 use tracing::debug;
 use validator::ValidationError;
@@ -1,2 +1,7 @@
 pub(super) mod cron;
+pub(super) mod prune;
 pub(super) mod records_group;
+pub(super) mod resolver;
+pub(super) mod retry;
+pub(super) mod schedule;
+pub(super) mod timezone;
@@ -0,0 +1,15 @@
+use crate::configuration::user::prune::PruneConfig;
+use validator::ValidationError;
+
+pub(crate) fn validate_prune_config(config: &PruneConfig) -> Result<(), ValidationError> {
+    match config.instance.as_deref() {
+        None if config.enabled => {
+            Err(ValidationError::new("Pruning needs an instance name, so only records of this instance are deleted."))
+        }
+        // The name is one word of the record comment.
+        Some(instance) if instance.is_empty() || instance.contains(char::is_whitespace) => {
+            Err(ValidationError::new("The instance name can't be empty or contain whitespace."))
+        }
+        _ => Ok(()),
+    }
+}
@@ -1,4 +1,4 @@
-use crate::configuration::user::records::RecordsGroup;
+use crate::configuration::user::records::{DnsType, RecordsGroup};
 use validator::ValidationError;
 
 pub(crate) fn validate_record_groups_schema(group: &RecordsGroup) -> Result<(), ValidationError> {
@@ -6,6 +6,15 @@ pub(crate) fn validate_record_groups_schema(group: &RecordsGroup) -> Result<(),
         return Err(ValidationError::new("Must provide a cloudflare config if you define Cloudflare records."));
     }
 
+    let needs =
+        |dns_type: DnsType| group.cloudflare.iter().any(|record| record.basic_record.dns_type.contains(&dns_type));
+    if needs(DnsType::A) && !group.resolver.supports_ipv4() {
+        return Err(ValidationError::new("The resolver of this group can't look up IPv4 addresses for A records."));
+    }
+    if needs(DnsType::Aaaa) && !group.resolver.supports_ipv6() {
+        return Err(ValidationError::new("The resolver of this group can't look up IPv6 addresses for AAAA records."));
+    }
+
     Ok(())
 }
 
@@ -13,6 +22,7 @@ pub(crate) fn validate_record_groups_schema(group: &RecordsGroup) -> Result<(),
 mod tests {
     use crate::configuration::user::providers::{Cloudflare, Providers};
     use crate::configuration::user::records::{BasicRecord, CloudflareRecord, DnsType, RecordsGroup};
+    use crate::configuration::user::resolver::Resolver;
     use crate::configuration::validation::records_group::validate_record_groups_schema;
 
     #[test]
@@ -23,6 +33,8 @@ mod tests {
             },
             cloudflare: vec![create_base_cloudflare_record()],
             resolver: Default::default(),
+            cron: None,
+            force_update_after: None,
         };
 
         assert!(validate_record_groups_schema(&group).is_ok());
@@ -36,6 +48,23 @@ mod tests {
             },
             cloudflare: vec![create_base_cloudflare_record()],
             resolver: Default::default(),
+            cron: None,
+            force_update_after: None,
+        };
+
+        assert!(validate_record_groups_schema(&group).is_err());
+    }
+
+    #[test]
+    fn test_ipv4_only_resolver_rejects_aaaa_records() {
+        let group = RecordsGroup {
+            providers: Providers {
+                cloudflare: Some(create_base_cloudflare_provider()),
+            },
+            cloudflare: vec![create_base_cloudflare_record()],
+            resolver: Resolver::AwsCheckip,
+            cron: None,
+            force_update_after: None,
         };
 
         assert!(validate_record_groups_schema(&group).is_err());
@@ -49,6 +78,8 @@ mod tests {
             },
             cloudflare: vec![],
             resolver: Default::default(),
+            cron: None,
+            force_update_after: None,
         };
 
         assert!(validate_record_groups_schema(&group).is_ok());
@@ -58,6 +89,7 @@ mod tests {
         Cloudflare {
             auth_token: "Some auth token".to_string(),
             zone_id: "My Zone id".to_string(),
+            requests_per_second: 4.0,
         }
     }
 
@@ -66,6 +98,8 @@ mod tests {
             name: "My Record".to_string(),
             ttl: 120,
             dns_type: vec![DnsType::Aaaa],
+            cron: None,
+            force_update_after: None,
         }
     }
 
@@ -73,6 +107,8 @@ mod tests {
         CloudflareRecord {
             basic_record: create_basic_record(),
             proxied: false,
+            on_duplicate: Default::default(),
+            require_ownership: false,
         }
     }
 }
@@ -0,0 +1,104 @@
+use crate::configuration::user::resolver::{
+    ConsensusResolverConfig, Extraction, FallbackResolverConfig, HttpResolverConfig, Resolver,
+};
+use reqwest::header::{HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use validator::ValidationError;
+
+pub(crate) fn validate_http_resolver(config: &HttpResolverConfig) -> Result<(), ValidationError> {
+    if config.ipv4_url.is_none() && config.ipv6_url.is_none() {
+        return Err(ValidationError::new("Must provide an ipv4_url, an ipv6_url or both."));
+    }
+
+    validate_json_pointer(&config.extract)
+}
+
+pub(crate) fn validate_fallback_resolver(config: &FallbackResolverConfig) -> Result<(), ValidationError> {
+    if config.resolvers.is_empty() {
+        return Err(ValidationError::new("A fallback resolver needs at least one resolver."));
+    }
+    if config.resolvers.iter().any(|resolver| matches!(resolver, Resolver::Fallback(_))) {
+        return Err(ValidationError::new("A fallback resolver can't contain another fallback resolver."));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_consensus_resolver(config: &ConsensusResolverConfig) -> Result<(), ValidationError> {
+    if config.quorum > config.resolvers.len() {
+        return Err(ValidationError::new("The quorum can't be larger than the number of resolvers."));
+    }
+    if config.resolvers.iter().any(|resolver| matches!(resolver, Resolver::Fallback(_) | Resolver::Consensus(_))) {
+        return Err(ValidationError::new("A consensus resolver can only contain single resolvers."));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_interface_name(name: &str) -> Result<(), ValidationError> {
+    // Linux limits interface names to 15 bytes (IFNAMSIZ without the null terminator).
+    if name.is_empty() || name.len() > 15 || name.contains(['/', ' ']) {
+        return Err(ValidationError::new("Invalid interface name, expected something like eth0 or ppp0"));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_headers(headers: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    for (name, value) in headers {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err() {
+            return Err(ValidationError::new("Invalid HTTP header"));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_json_pointer(extraction: &Extraction) -> Result<(), ValidationError> {
+    match extraction {
+        Extraction::JsonPointer(pointer) if !pointer.is_empty() && !pointer.starts_with('/') => {
+            Err(ValidationError::new("A JSON pointer must start with a slash, e.g. /ip"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::user::resolver::Extraction;
+    use crate::configuration::validation::resolver::{
+        validate_headers, validate_interface_name, validate_json_pointer,
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_valid_headers() {
+        let headers = BTreeMap::from([("X-Api-Key".to_string(), "secret".to_string())]);
+
+        assert!(validate_headers(&headers).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        let name = BTreeMap::from([("X Api Key".to_string(), "secret".to_string())]);
+        let value = BTreeMap::from([("X-Api-Key".to_string(), "line\nbreak".to_string())]);
+
+        assert!(validate_headers(&name).is_err());
+        assert!(validate_headers(&value).is_err());
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        assert!(validate_json_pointer(&Extraction::JsonPointer("/data/ip".to_string())).is_ok());
+        assert!(validate_json_pointer(&Extraction::JsonPointer("ip".to_string())).is_err());
+        assert!(validate_json_pointer(&Extraction::Body).is_ok());
+    }
+
+    #[test]
+    fn test_interface_name() {
+        assert!(validate_interface_name("ppp0").is_ok());
+        assert!(validate_interface_name("").is_err());
+        assert!(validate_interface_name("a-very-long-interface").is_err());
+        assert!(validate_interface_name("eth0/1").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+use crate::configuration::user::retry::RetryConfig;
+use validator::ValidationError;
+
+pub(crate) fn validate_retry_config(config: &RetryConfig) -> Result<(), ValidationError> {
+    if config.base_delay > config.max_delay {
+        return Err(ValidationError::new("The base_delay can't be longer than the max_delay."));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::configuration::user::config::Config;
+use std::time::Duration;
+use validator::ValidationError;
+
+/**
+Shorter intervals would run into the rate limits of the IP resolvers and the Cloudflare API.
+*/
+pub(crate) const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) fn validate_interval(interval: &Duration) -> Result<(), ValidationError> {
+    if *interval < MIN_INTERVAL {
+        return Err(ValidationError::new("The interval must be at least 30 seconds"));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_config_schedule(config: &Config) -> Result<(), ValidationError> {
+    match (&config.cron, &config.interval) {
+        (Some(_), Some(_)) => return Err(ValidationError::new("Set either cron or interval, not both.")),
+        (None, None) => return Err(ValidationError::new("Must provide either a cron or an interval.")),
+        _ => {}
+    }
+
+    if let Some(splay) = config.splay {
+        match config.interval {
+            None => return Err(ValidationError::new("The splay only works together with an interval.")),
+            Some(interval) if splay >= interval => {
+                return Err(ValidationError::new("The splay must be shorter than the interval."));
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::config::Config;
+    use crate::configuration::validation::schedule::{validate_config_schedule, validate_interval};
+    use std::time::Duration;
+
+    #[test]
+    fn test_interval_minimum() {
+        assert!(validate_interval(&Duration::from_secs(30)).is_ok());
+        assert!(validate_interval(&Duration::from_secs(5)).is_err());
+    }
+
+    #[test]
+    fn test_cron_or_interval() {
+        let cron: Config = toml::from_str("cron = \"0 * * * * *\"\ndomains = []").unwrap();
+        let interval: Config = toml::from_str("interval = \"5m\"\nsplay = \"30s\"\ndomains = []").unwrap();
+        let both: Config = toml::from_str("cron = \"0 * * * * *\"\ninterval = \"5m\"\ndomains = []").unwrap();
+        let none: Config = toml::from_str("domains = []").unwrap();
+
+        assert!(validate_config_schedule(&cron).is_ok());
+        assert!(validate_config_schedule(&interval).is_ok());
+        assert!(validate_config_schedule(&both).is_err());
+        assert!(validate_config_schedule(&none).is_err());
+    }
+
+    #[test]
+    fn test_invalid_splay() {
+        let without_interval: Config = toml::from_str("cron = \"0 * * * * *\"\nsplay = \"30s\"\ndomains = []").unwrap();
+        let too_long: Config = toml::from_str("interval = \"1m\"\nsplay = \"1m\"\ndomains = []").unwrap();
+
+        assert!(validate_config_schedule(&without_interval).is_err());
+        assert!(validate_config_schedule(&too_long).is_err());
+    }
+}
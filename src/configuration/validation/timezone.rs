@@ -0,0 +1,31 @@
+use chrono_tz::Tz;
+use tracing::debug;
+use validator::ValidationError;
+
+pub(crate) fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    match timezone.parse::<Tz>() {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            debug!("Invalid timezone: {}", err);
+
+            Err(ValidationError::new("Invalid timezone, expected an IANA name like Europe/Berlin"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::validation::timezone::validate_timezone;
+
+    #[test]
+    fn test_valid_timezone() {
+        assert!(validate_timezone("Europe/Berlin").is_ok());
+        assert!(validate_timezone("UTC").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_timezone() {
+        assert!(validate_timezone("Mars/Olympus_Mons").is_err());
+        assert!(validate_timezone("+02:00").is_err());
+    }
+}
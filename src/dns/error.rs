@@ -0,0 +1,168 @@
+use crate::ip::resolver::error::ResolverError;
+use crate::retry::TransientError;
+use cloudflare::framework::response::ApiFailure;
+use std::time::Duration;
+use thiserror::Error;
+
+/**
+Everything that can go wrong while syncing a record.
+Each kind is either transient (worth another try later) or permanent (needs a fix in the config or the zone).
+*/
+#[derive(Debug, Error)]
+pub(crate) enum SyncError {
+    #[error("Could not resolve the public IP address: {0}")]
+    Resolver(#[from] ResolverError),
+    #[error("Authentication with the DNS provider failed: {0}")]
+    Auth(String),
+    /**
+    Backs off like any other transient error. The cloudflare crate drops the response headers,
+    so there is no `Retry-After` to wait for.
+    */
+    #[error("Rate limited by the DNS provider: {0}")]
+    RateLimited(String),
+    #[error("Conflicting DNS records: {0}")]
+    Conflict(String),
+    #[error("DNS record not found: {0}")]
+    NotFound(String),
+    #[error("Could not reach the DNS provider: {0}")]
+    Transport(String),
+    #[error("The DNS provider rejected the request: {0}")]
+    Rejected(String),
+    #[error("Refusing to delete {count} records because at most {max} deletions are allowed per run")]
+    TooManyDeletions {
+        count: usize,
+        max: usize,
+    },
+}
+
+impl SyncError {
+    pub(crate) fn is_transient(&self) -> bool {
+        match self {
+            SyncError::Resolver(err) => err.is_transient(),
+            SyncError::RateLimited(_) | SyncError::Transport(_) => true,
+            SyncError::Auth(_)
+            | SyncError::Conflict(_)
+            | SyncError::NotFound(_)
+            | SyncError::Rejected(_)
+            | SyncError::TooManyDeletions {
+                ..
+            } => false,
+        }
+    }
+}
+
+impl TransientError for SyncError {
+    fn is_transient(&self) -> bool {
+        SyncError::is_transient(self)
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SyncError::Resolver(err) => err.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+// Cloudflare error codes that tell us more than the HTTP status.
+// The DNS specific codes (81xxx) don't fit into the `u16` of the cloudflare crate, so we can only rely on the status.
+const CLOUDFLARE_AUTH_ERROR_CODES: [u16; 3] = [9103, 9109, 10000];
+
+impl From<ApiFailure> for SyncError {
+    fn from(failure: ApiFailure) -> Self {
+        let message = failure.to_string();
+        match failure {
+            ApiFailure::Error(status, errors) => {
+                let has_code = |codes: &[u16]| errors.errors.iter().any(|err| codes.contains(&err.code));
+
+                if status.as_u16() == 401 || status.as_u16() == 403 || has_code(&CLOUDFLARE_AUTH_ERROR_CODES) {
+                    SyncError::Auth(message)
+                } else if status.as_u16() == 429 {
+                    SyncError::RateLimited(message)
+                } else if status.as_u16() == 404 {
+                    SyncError::NotFound(message)
+                } else if status.as_u16() == 409 {
+                    SyncError::Conflict(message)
+                } else if status.is_server_error() || status.as_u16() == 408 {
+                    SyncError::Transport(message)
+                } else {
+                    SyncError::Rejected(message)
+                }
+            }
+            // The cloudflare crate brings its own version of reqwest, so we can only look at what it tells us.
+            ApiFailure::Invalid(err) if err.is_timeout() || err.is_connect() || err.is_request() => {
+                SyncError::Transport(message)
+            }
+            ApiFailure::Invalid(_) => SyncError::Rejected(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dns::error::SyncError;
+    use cloudflare::framework::response::{ApiErrors, ApiFailure, ResponseInfo};
+    use reqwest::StatusCode;
+
+    fn create_failure(status: StatusCode, code: u16) -> ApiFailure {
+        ApiFailure::Error(
+            status,
+            ApiErrors {
+                other: Default::default(),
+                errors: vec![ResponseInfo {
+                    code,
+                    message: "Something went wrong".to_string(),
+                    other: Default::default(),
+                }],
+            },
+        )
+    }
+
+    #[test]
+    fn test_auth_failures_are_permanent() {
+        let err = SyncError::from(create_failure(StatusCode::FORBIDDEN, 10000));
+
+        assert!(matches!(err, SyncError::Auth(_)));
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn test_rate_limits_are_transient() {
+        let err = SyncError::from(create_failure(StatusCode::TOO_MANY_REQUESTS, 971));
+
+        assert!(matches!(err, SyncError::RateLimited(_)));
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn test_server_errors_are_transient() {
+        let err = SyncError::from(create_failure(StatusCode::SERVICE_UNAVAILABLE, 0));
+
+        assert!(matches!(err, SyncError::Transport(_)));
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn test_record_not_found() {
+        let err = SyncError::from(create_failure(StatusCode::NOT_FOUND, 1032));
+
+        assert!(matches!(err, SyncError::NotFound(_)));
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn test_conflict() {
+        let err = SyncError::from(create_failure(StatusCode::CONFLICT, 1000));
+
+        assert!(matches!(err, SyncError::Conflict(_)));
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn test_validation_errors_are_rejected() {
+        let err = SyncError::from(create_failure(StatusCode::BAD_REQUEST, 9005));
+
+        assert!(matches!(err, SyncError::Rejected(_)));
+        assert!(!err.is_transient());
+    }
+}
@@ -1 +1,5 @@
+pub(crate) mod error;
+pub(crate) mod plan;
+pub(crate) mod prune;
 pub(crate) mod record;
+pub(crate) mod report;
@@ -0,0 +1,123 @@
+use crate::configuration::user::records::DnsType;
+use crate::dns::error::SyncError;
+use crate::dns::record::CachedRecord;
+use cloudflare::endpoints::dns::dns::DnsContent;
+use std::fmt::Display;
+
+/**
+A change that would bring a single record of a single type in line with the config.
+A record can need more than one change when duplicates exist.
+Planning only reads from the provider, so a plan can be shown to the user without touching anything.
+*/
+#[derive(Debug)]
+pub(crate) struct PlannedChange {
+    pub(crate) name: String,
+    pub(crate) dns_type: DnsType,
+    pub(crate) action: PlannedAction,
+    pub(crate) desired: DnsContent,
+}
+
+#[derive(Debug)]
+pub(crate) enum PlannedAction {
+    Create,
+    Update {
+        current: CachedRecord,
+    },
+    Unchanged {
+        current: CachedRecord,
+    },
+    /**
+    Removes a duplicate record.
+    */
+    Delete {
+        current: CachedRecord,
+    },
+}
+
+/**
+A row of the dry run.
+Records that can't be planned are shown with their error, so a single one of them doesn't hide the others.
+*/
+#[derive(Debug)]
+pub(crate) enum PlanEntry {
+    Change(PlannedChange),
+    /**
+    A change the sync would refuse to make, e.g. a deletion beyond `max_deletions`.
+    */
+    Blocked {
+        change: PlannedChange,
+        reason: SyncError,
+    },
+    Failed {
+        name: String,
+        dns_type: DnsType,
+        error: SyncError,
+    },
+}
+
+impl PlanEntry {
+    pub(crate) fn is_failure(&self) -> bool {
+        !matches!(self, PlanEntry::Change(_))
+    }
+}
+
+impl PlannedAction {
+    pub(crate) fn current(&self) -> Option<&CachedRecord> {
+        match self {
+            PlannedAction::Create => None,
+            PlannedAction::Update {
+                current,
+            }
+            | PlannedAction::Unchanged {
+                current,
+            }
+            | PlannedAction::Delete {
+                current,
+            } => Some(current),
+        }
+    }
+}
+
+impl Display for PlannedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlannedAction::Create => write!(f, "create"),
+            PlannedAction::Update {
+                ..
+            } => write!(f, "update"),
+            PlannedAction::Unchanged {
+                ..
+            } => write!(f, "unchanged"),
+            PlannedAction::Delete {
+                ..
+            } => write!(f, "delete"),
+        }
+    }
+}
+
+pub(crate) fn format_dns_content(content: &DnsContent) -> String {
+    match content {
+        DnsContent::A {
+            content,
+        } => content.to_string(),
+        DnsContent::AAAA {
+            content,
+        } => content.to_string(),
+        DnsContent::CNAME {
+            content,
+        }
+        | DnsContent::NS {
+            content,
+        }
+        | DnsContent::MX {
+            content,
+            ..
+        }
+        | DnsContent::TXT {
+            content,
+        }
+        | DnsContent::SRV {
+            content,
+        } => content.clone(),
+    }
+}
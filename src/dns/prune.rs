@@ -0,0 +1,307 @@
+use crate::cloudflare_api::CloudflareClient;
+use crate::cloudflare_api::dns::{DnsRecord, ListDnsRecords, ListDnsRecordsParams};
+use crate::configuration::user::providers::Cloudflare;
+use crate::configuration::user::records::DnsType;
+use crate::dns::error::SyncError;
+use crate::dns::plan::{PlannedAction, PlannedChange};
+use crate::dns::record::CachedRecord;
+use crate::dns::report::SyncOutcome;
+use crate::io_helper::CliWriter;
+use crate::retry::RetryPolicy;
+use cloudflare::endpoints::dns::dns::DeleteDnsRecord;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const PAGE_SIZE: u32 = 100;
+
+/**
+The names and types of all records configured for a zone.
+*/
+pub(crate) type WantedRecords = HashSet<(String, DnsType)>;
+
+/**
+Finds and deletes the records this instance created in a zone that are no longer in the config.
+Records without the ownership marker or with the name of another instance are never touched.
+*/
+pub(crate) struct CloudflarePruner {
+    client: Arc<CloudflareClient>,
+    provider: Arc<Cloudflare>,
+    instance: String,
+    wanted: WantedRecords,
+    writer: Arc<CliWriter>,
+    retry: RetryPolicy,
+}
+
+impl CloudflarePruner {
+    pub(crate) fn new(
+        client: Arc<CloudflareClient>,
+        provider: Arc<Cloudflare>,
+        instance: String,
+        wanted: WantedRecords,
+        writer: Arc<CliWriter>,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            client,
+            provider,
+            instance,
+            wanted: wanted.into_iter().map(|(name, dns_type)| (normalize_name(&name), dns_type)).collect(),
+            writer,
+            retry,
+        }
+    }
+
+    pub(crate) fn zone_id(&self) -> &str {
+        &self.provider.zone_id
+    }
+
+    /**
+    Lists the records of the given type created by this instance that would be deleted, without changing anything.
+    */
+    pub(crate) fn plan(&self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+        self.writer.debug(format!("Searching for {} records to prune in zone {}", dns_type, self.provider.zone_id));
+
+        Ok(self
+            .list_records(dns_type)?
+            .into_iter()
+            .filter(|record| record.is_owned_by(&self.instance))
+            .filter(|record| !self.wanted.contains(&(normalize_name(&record.name), *dns_type)))
+            .map(|record| {
+                let name = record.name.clone();
+                let current = CachedRecord::from(record);
+                PlannedChange {
+                    name,
+                    dns_type: *dns_type,
+                    desired: current.content.clone(),
+                    action: PlannedAction::Delete {
+                        current,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    pub(crate) fn apply(&self, change: PlannedChange) -> Result<SyncOutcome, SyncError> {
+        let PlannedAction::Delete {
+            current,
+        } = change.action
+        else {
+            return Ok(SyncOutcome::Unchanged);
+        };
+
+        let endpoint = DeleteDnsRecord {
+            zone_identifier: self.provider.zone_id.as_str(),
+            identifier: current.id.as_str(),
+        };
+        match self.retry.run("Pruning the DNS record", || self.client.request(&endpoint).map_err(SyncError::from)) {
+            Ok(_) | Err(SyncError::NotFound(_)) => {}
+            Err(err) => return Err(err),
+        }
+        self.writer.info(format!("{} record for {} pruned", change.dns_type, change.name));
+
+        Ok(SyncOutcome::Deleted)
+    }
+
+    fn list_records(&self, dns_type: &DnsType) -> Result<Vec<DnsRecord>, SyncError> {
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let endpoint = ListDnsRecords {
+                zone_identifier: self.provider.zone_id.as_str(),
+                params: ListDnsRecordsParams {
+                    record_type: Some(*dns_type),
+                    page: Some(page),
+                    per_page: Some(PAGE_SIZE),
+                    ..Default::default()
+                },
+            };
+            let rs = self
+                .retry
+                .run("Listing the DNS records", || self.client.request(&endpoint).map_err(SyncError::from))?;
+            let total_pages = rs
+                .result_info
+                .as_ref()
+                .and_then(|info| info.get("total_pages"))
+                .and_then(|total_pages| total_pages.as_u64())
+                .unwrap_or(1);
+            records.extend(rs.result.0);
+
+            if u64::from(page) >= total_pages {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+}
+
+/**
+Cloudflare returns names in lowercase and without the trailing dot, the config might not.
+*/
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::Verbosity;
+    use crate::cloudflare_api::CloudflareClient;
+    use crate::configuration::user::providers::Cloudflare;
+    use crate::configuration::user::records::DnsType;
+    use crate::configuration::user::retry::RetryConfig;
+    use crate::dns::plan::PlannedAction;
+    use crate::dns::prune::CloudflarePruner;
+    use crate::dns::report::SyncOutcome;
+    use crate::io_helper::CliWriter;
+    use crate::rate_limit::RateLimiter;
+    use crate::retry::RetryPolicy;
+    use cloudflare::framework::Environment;
+    use cloudflare::framework::auth::Credentials;
+    use cloudflare::framework::client::ClientConfig;
+    use cloudflare::framework::client::blocking_api::HttpApiClient;
+    use mockito::{Matcher, Server, ServerGuard};
+    use serde_json::json;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    const OURS: &str = "managed-by=dyncloud dyncloud-instance=home";
+
+    #[test]
+    fn test_plan_only_prunes_owned_records_missing_from_the_config() {
+        let mut server = Server::new();
+        let mock = server
+            .mock("GET", "/zones/ZoneID/dns_records")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("type".to_string(), "AAAA".to_string()),
+                Matcher::UrlEncoded("page".to_string(), "1".to_string()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({
+                  "result": [
+                    create_dns_record_json("kept", "Kept.Example.Internal", Some(OURS)),
+                    create_dns_record_json("removed", "removed.example.internal", Some(OURS)),
+                    create_dns_record_json("foreign", "foreign.example.internal", None),
+                  ],
+                  "success": true,
+                  "errors": [],
+                  "messages": [],
+                  "result_info": {"page": 1, "per_page": 100, "count": 3, "total_count": 3, "total_pages": 1}
+                })
+                .to_string(),
+            )
+            .create();
+
+        let pruner = create_test_pruner(&server);
+        let changes = pruner.plan(&DnsType::Aaaa).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].name, "removed.example.internal");
+        assert!(matches!(&changes[0].action, PlannedAction::Delete { current } if current.id == "removed"));
+
+        mock.assert();
+    }
+
+    #[test]
+    fn test_plan_leaves_records_of_other_instances_alone() {
+        let mut server = Server::new();
+        let mock = server
+            .mock("GET", Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({
+                  "result": [
+                    create_dns_record_json("other", "other.example.internal", Some("managed-by=dyncloud dyncloud-instance=office")),
+                    create_dns_record_json("unnamed", "unnamed.example.internal", Some("managed-by=dyncloud")),
+                    create_dns_record_json("removed", "removed.example.internal", Some(OURS)),
+                  ],
+                  "success": true,
+                  "errors": [],
+                  "messages": []
+                })
+                .to_string(),
+            )
+            .create();
+
+        let pruner = create_test_pruner(&server);
+        let changes = pruner.plan(&DnsType::Aaaa).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].name, "removed.example.internal");
+
+        mock.assert();
+    }
+
+    #[test]
+    fn test_apply_deletes_the_record() {
+        let mut server = Server::new();
+        let mock = server
+            .mock("DELETE", "/zones/ZoneID/dns_records/removed")
+            .with_status(200)
+            .with_body(json!({"result": {"id": "removed"}, "success": true, "errors": [], "messages": []}).to_string())
+            .create();
+        let list_mock = server
+            .mock("GET", Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({
+                  "result": [create_dns_record_json("removed", "removed.example.internal", Some(OURS))],
+                  "success": true,
+                  "errors": [],
+                  "messages": []
+                })
+                .to_string(),
+            )
+            .create();
+
+        let pruner = create_test_pruner(&server);
+        let change = pruner.plan(&DnsType::Aaaa).unwrap().remove(0);
+
+        assert_eq!(pruner.apply(change).unwrap(), SyncOutcome::Deleted);
+
+        mock.assert();
+        list_mock.assert();
+    }
+
+    fn create_test_pruner(server: &ServerGuard) -> CloudflarePruner {
+        let http_client = HttpApiClient::new(
+            Credentials::UserAuthToken {
+                token: "CustomAuthToken".to_string(),
+            },
+            ClientConfig::default(),
+            Environment::Custom(server.url()),
+        )
+        .unwrap();
+
+        CloudflarePruner::new(
+            Arc::new(CloudflareClient::new(http_client, RateLimiter::new(100.0))),
+            Arc::new(Cloudflare {
+                auth_token: "CustomAuthToken".to_string(),
+                zone_id: "ZoneID".to_string(),
+                requests_per_second: 4.0,
+            }),
+            "home".to_string(),
+            HashSet::from([("kept.example.internal.".to_string(), DnsType::Aaaa)]),
+            Arc::new(CliWriter::new(&Verbosity::default())),
+            RetryPolicy::from(&RetryConfig::default()),
+        )
+    }
+
+    fn create_dns_record_json(id: &str, name: &str, comment: Option<&str>) -> serde_json::Value {
+        json!({
+          "id": id,
+          "name": name,
+          "type": "AAAA",
+          "content": "::1",
+          "proxiable": true,
+          "proxied": false,
+          "ttl": 60,
+          "settings": {},
+          "meta": {},
+          "comment": comment,
+          "tags": [],
+          "created_on": "2024-12-27T16:33:47.054786Z",
+          "modified_on": "2025-10-08T20:48:29.15931Z"
+        })
+    }
+}
@@ -1,37 +1,144 @@
+use crate::cloudflare_api::CloudflareClient;
+use crate::cloudflare_api::dns::{
+    CreateDnsRecord, DnsRecord, DnsRecordParams, ListDnsRecords, ListDnsRecordsParams, UpdateDnsRecord, is_owned,
+    ownership_comment,
+};
 use crate::configuration::user::providers::Cloudflare;
-use crate::configuration::user::records::{CloudflareRecord as CloudflareConfigRecord, DnsType};
+use crate::configuration::user::records::{CloudflareRecord as CloudflareConfigRecord, DnsType, OnDuplicate};
+use crate::dns::error::SyncError;
+use crate::dns::plan::{PlannedAction, PlannedChange};
+use crate::dns::report::{RecordReport, SyncOutcome};
 use crate::io_helper::CliWriter;
 use crate::ip::resolver::IpResolver;
-use anyhow::{Error, anyhow};
-use cloudflare::endpoints::dns::dns::{
-    CreateDnsRecord, CreateDnsRecordParams, DnsContent, DnsRecord, ListDnsRecords, ListDnsRecordsParams,
-    UpdateDnsRecord, UpdateDnsRecordParams,
-};
-use cloudflare::framework::client::blocking_api::HttpApiClient;
+use crate::retry::RetryPolicy;
+use crate::state::store::{StateStore, cloudflare_state_key};
+use chrono::Utc;
+use cloudflare::endpoints::dns::dns::{DeleteDnsRecord, DnsContent};
 use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
 pub(crate) trait SyncableRecord: Send {
-    fn sync(&mut self, progress_bar: &Option<ProgressBar>) -> Result<(), Error>;
+    fn dns_types(&self) -> Vec<DnsType>;
+
+    /**
+    Figures out what has to be done to sync the record of the given type, without changing anything.
+    */
+    fn plan(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError>;
+
+    /**
+    Like [`Self::plan`], but ignores everything cached and always looks up the live records. Used by the dry run.
+    */
+    fn plan_live(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError>;
+
+    /**
+    Makes a planned change. This is usually a single outcome, but a record that was deleted outside of dyncloud is
+    planned again, which can take several changes.
+    */
+    fn apply(&mut self, change: PlannedChange) -> Vec<Result<SyncOutcome, SyncError>>;
+
+    fn name(&self) -> &str;
+
+    /**
+    Whether the record has to be written again even though the IP did not change, see `force_update_after`.
+    */
+    fn is_refresh_due(&self) -> bool {
+        false
+    }
+
+    /**
+    Syncs all types of the record. A failing type does not stop the others from being synced.
+    */
+    fn sync(&mut self, progress_bar: &Option<ProgressBar>) -> Vec<RecordReport> {
+        let mut reports = Vec::new();
+        for dns_type in self.dns_types() {
+            match self.plan(&dns_type) {
+                Ok(changes) => {
+                    for change in changes {
+                        for result in self.apply(change) {
+                            reports.push(RecordReport {
+                                name: self.name().to_string(),
+                                dns_type,
+                                result,
+                            });
+                        }
+                    }
+                }
+                Err(err) => reports.push(RecordReport {
+                    name: self.name().to_string(),
+                    dns_type,
+                    result: Err(err),
+                }),
+            }
+
+            if let Some(progress_bar) = progress_bar {
+                progress_bar.inc(1);
+            }
+        }
+
+        reports
+    }
+}
+
+/**
+The last known state of a record on Cloudflare.
+It comes from the lookup or from the response of our last write and saves the lookup before an update.
+Skipping a no-op update is only ever decided on the live record.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedRecord {
+    pub(crate) id: String,
+    #[serde(flatten)]
+    pub(crate) content: DnsContent,
+    pub(crate) ttl: u32,
+    pub(crate) proxied: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) comment: Option<String>,
+}
+
+impl CachedRecord {
+    pub(crate) fn is_owned(&self) -> bool {
+        is_owned(self.comment.as_deref())
+    }
+}
+
+impl From<DnsRecord> for CachedRecord {
+    fn from(record: DnsRecord) -> Self {
+        Self {
+            id: record.id,
+            content: record.content,
+            ttl: record.ttl,
+            proxied: record.proxied,
+            comment: record.comment,
+        }
+    }
 }
 
 pub(crate) struct CloudflareRecord {
     pub(crate) ip_resolver: Arc<Box<dyn IpResolver>>,
-    pub(crate) client: Arc<HttpApiClient>,
+    pub(crate) client: Arc<CloudflareClient>,
     pub(crate) provider: Arc<Cloudflare>,
     pub(crate) record: CloudflareConfigRecord,
     pub(crate) writer: Arc<CliWriter>,
-    pub(crate) id_cache: HashMap<DnsType, String>,
+    pub(crate) state: Arc<StateStore>,
+    pub(crate) retry: RetryPolicy,
+    /**
+    The comment of the records we create. It marks them as ours and names the instance, if one is set.
+    */
+    pub(crate) comment: String,
+    pub(crate) id_cache: HashMap<DnsType, Vec<CachedRecord>>,
 }
 
 impl CloudflareRecord {
     pub(crate) fn new(
         ip_resolver: Arc<Box<dyn IpResolver>>,
-        client: Arc<HttpApiClient>,
+        client: Arc<CloudflareClient>,
         provider: Arc<Cloudflare>,
         record: CloudflareConfigRecord,
         writer: Arc<CliWriter>,
+        state: Arc<StateStore>,
+        retry: RetryPolicy,
     ) -> Self {
         Self {
             ip_resolver,
@@ -39,59 +146,206 @@ impl CloudflareRecord {
             provider,
             record,
             writer,
+            state,
+            retry,
+            comment: ownership_comment(None),
             id_cache: HashMap::new(),
         }
     }
 
-    fn sync_dns_record(&mut self, dns_type: &DnsType) -> Result<(), Error> {
-        let id = self.get_record_id(dns_type)?;
+    /**
+    Names the instance in the comment of the records we create, so only this instance prunes them.
+    */
+    pub(crate) fn with_instance(mut self, instance: Option<&str>) -> Self {
+        self.comment = ownership_comment(instance);
+        self
+    }
+
+    fn get_cached_records(&mut self, dns_type: &DnsType) -> Option<Vec<CachedRecord>> {
+        if let Some(records) = self.id_cache.get(dns_type) {
+            return Some(records.clone());
+        }
+
+        if let Some(state) = self.state.get(&self.state_key(dns_type))
+            && !state.records.is_empty()
+        {
+            self.writer.debug(format!(
+                "Using {} {} record(s) for {} from the state file",
+                state.records.len(),
+                dns_type,
+                self.record.basic_record.name
+            ));
+            self.id_cache.insert(*dns_type, state.records.clone());
+
+            return Some(state.records);
+        }
+
+        None
+    }
+
+    fn remember(&mut self, dns_type: &DnsType, record: CachedRecord, written: bool) {
+        let key = self.state_key(dns_type);
+        let records = self.id_cache.entry(*dns_type).or_default();
+        match records.iter_mut().find(|cached| cached.id == record.id) {
+            Some(cached) => *cached = record,
+            None => records.push(record),
+        }
+        match written {
+            true => self.state.set_written(key, records.clone(), Utc::now()),
+            false => self.state.set(key, records.clone()),
+        }
+    }
 
-        self.update_dns_record(id.as_str(), dns_type)?;
-        Ok(())
+    fn forget(&mut self, dns_type: &DnsType, id: &str) {
+        let key = self.state_key(dns_type);
+        let records = self.id_cache.entry(*dns_type).or_default();
+        records.retain(|cached| cached.id != id);
+        self.state.set(key, records.clone());
+    }
+
+    fn state_key(&self, dns_type: &DnsType) -> String {
+        cloudflare_state_key(&self.provider.zone_id, &self.record.basic_record.name, dns_type)
+    }
+
+    /**
+    A record that we never wrote ourselves, as far as the state knows, is due as well.
+    */
+    fn is_type_refresh_due(&self, dns_type: &DnsType) -> bool {
+        let Some(force_update_after) = self.record.basic_record.force_update_after else {
+            return false;
+        };
+
+        match self.state.get(&self.state_key(dns_type)).and_then(|state| state.written_at) {
+            None => true,
+            Some(written_at) => {
+                Utc::now().signed_duration_since(written_at).to_std().is_ok_and(|age| age >= force_update_after)
+            }
+        }
+    }
+
+    /**
+    With `require_ownership`, records that were not created by dyncloud must not be touched.
+    The `update-owned` policy already leaves them alone, as long as there is at least one record of our own.
+    */
+    fn check_ownership(&self, dns_type: &DnsType, records: &[CachedRecord]) -> Result<(), SyncError> {
+        if !self.record.require_ownership {
+            return Ok(());
+        }
+
+        let foreign = records.iter().filter(|record| !record.is_owned()).count();
+        if foreign == 0 || (self.record.on_duplicate == OnDuplicate::UpdateOwned && foreign < records.len()) {
+            return Ok(());
+        }
+
+        Err(SyncError::Conflict(format!(
+            "{} existing {} record(s) for {} are not managed by dyncloud and require_ownership is enabled",
+            foreign, dns_type, self.record.basic_record.name
+        )))
+    }
+
+    /**
+    Splits the existing records into the ones to sync and the ones to delete, according to the `on_duplicate` policy.
+    */
+    fn handle_duplicates(
+        &self,
+        dns_type: &DnsType,
+        mut records: Vec<CachedRecord>,
+    ) -> Result<(Vec<CachedRecord>, Vec<CachedRecord>), SyncError> {
+        if records.len() <= 1 {
+            return Ok((records, Vec::new()));
+        }
+
+        match self.record.on_duplicate {
+            OnDuplicate::Fail => Err(SyncError::Conflict(format!(
+                "Found {} {} records for {} but expected at most one",
+                records.len(),
+                dns_type,
+                self.record.basic_record.name
+            ))),
+            OnDuplicate::UpdateAll => Ok((records, Vec::new())),
+            OnDuplicate::KeepFirst => {
+                let duplicates = records.split_off(1);
+                Ok((records, duplicates))
+            }
+            OnDuplicate::UpdateOwned => {
+                let (owned, foreign): (Vec<_>, Vec<_>) = records.into_iter().partition(CachedRecord::is_owned);
+                if owned.is_empty() {
+                    return Err(SyncError::Conflict(format!(
+                        "Found {} {} records for {} but none of them is managed by dyncloud",
+                        foreign.len(),
+                        dns_type,
+                        self.record.basic_record.name
+                    )));
+                }
+
+                self.writer.debug(format!(
+                    "Ignoring {} {} record(s) for {} that are not managed by dyncloud",
+                    foreign.len(),
+                    dns_type,
+                    self.record.basic_record.name
+                ));
+                Ok((owned, Vec::new()))
+            }
+        }
     }
 
-    fn update_dns_record(&self, id: &str, dns_type: &DnsType) -> Result<(), Error> {
-        self.client.request(&UpdateDnsRecord {
+    fn update_dns_record(&self, current: &CachedRecord, content: DnsContent) -> Result<DnsRecord, SyncError> {
+        let endpoint = UpdateDnsRecord {
             zone_identifier: self.provider.zone_id.as_str(),
-            identifier: id,
-            params: UpdateDnsRecordParams {
-                ttl: Some(self.record.basic_record.ttl),
-                proxied: Some(self.record.proxied),
+            identifier: current.id.as_str(),
+            params: DnsRecordParams {
+                ttl: self.record.basic_record.ttl,
+                proxied: self.record.proxied,
                 name: self.record.basic_record.name.as_str(),
-                content: self.build_dns_content(dns_type)?,
+                content,
+                comment: current.comment.as_deref(),
             },
-        })?;
+        };
+        let rs =
+            self.retry.run("Updating the DNS record", || self.client.request(&endpoint).map_err(SyncError::from))?;
+
+        Ok(rs.result)
+    }
+
+    fn delete_dns_record(&self, id: &str) -> Result<(), SyncError> {
+        let endpoint = DeleteDnsRecord {
+            zone_identifier: self.provider.zone_id.as_str(),
+            identifier: id,
+        };
+        self.retry.run("Deleting the DNS record", || self.client.request(&endpoint).map_err(SyncError::from))?;
 
         Ok(())
     }
 
-    fn get_record_id(&mut self, dns_type: &DnsType) -> Result<String, Error> {
-        match self.id_cache.get(dns_type) {
-            None => self.resolve_record_id(dns_type),
-            Some(id) => Ok(id.clone()),
+    fn is_up_to_date(&self, record: &CachedRecord, content: &DnsContent) -> bool {
+        if !is_same_content(&record.content, content) || record.proxied != self.record.proxied {
+            return false;
         }
+
+        // Cloudflare always uses the automatic TTL for proxied records, so the configured one can never match.
+        record.proxied || record.ttl == self.record.basic_record.ttl
     }
 
-    fn resolve_record_id(&mut self, dns_type: &DnsType) -> Result<String, Error> {
+    fn resolve_records(&mut self, dns_type: &DnsType) -> Result<Vec<CachedRecord>, SyncError> {
         self.writer.debug(format!(
-            "Searching for existing {} record for {}",
+            "Searching for existing {} records for {}",
             dns_type,
             self.record.basic_record.name.as_str()
         ));
-        let rs = self.client.request(&ListDnsRecords {
+        let endpoint = ListDnsRecords {
             zone_identifier: self.provider.zone_id.as_str(),
             params: ListDnsRecordsParams {
-                record_type: None,
                 name: Some(self.record.basic_record.name.clone()),
-                page: None,
-                per_page: None,
-                order: None,
-                direction: None,
-                search_match: None,
+                ..Default::default()
             },
-        })?;
-        let results: Vec<DnsRecord> = rs
+        };
+        let rs = self
+            .retry
+            .run("Searching for the DNS record", || self.client.request(&endpoint).map_err(SyncError::from))?;
+
+        Ok(rs
             .result
+            .0
             .into_iter()
             .filter(|record| match (dns_type, &record.content) {
                 (
@@ -108,54 +362,148 @@ impl CloudflareRecord {
                 ) => true,
                 (_, _) => false,
             })
-            .collect();
-
-        match results.len() {
-            0 => {
-                let record = self.create_new_dns_record(dns_type)?;
-                self.id_cache.insert(*dns_type, record.id.clone());
-
-                Ok(record.id)
-            }
-            1 => {
-                let id = results[0].id.clone();
-                self.id_cache.insert(*dns_type, id.clone());
-
-                Ok(id)
-            }
-            len => {
-                self.writer.error(format!(
-                    "DNS Search for {} resulted in more than 1 result. ({} results)",
-                    self.record.basic_record.name.as_str(),
-                    len
-                ));
-
-                Err(anyhow!("Invalid search result length!"))
-            }
-        }
+            .map(CachedRecord::from)
+            .collect())
     }
 
-    fn create_new_dns_record(&self, dns_type: &DnsType) -> Result<DnsRecord, Error> {
+    fn create_new_dns_record(&self, dns_type: &DnsType, content: DnsContent) -> Result<DnsRecord, SyncError> {
         self.writer.debug(format!(
             "No existing {:?} record found for {}. Creating a new one.",
             dns_type,
             self.record.basic_record.name.as_str()
         ));
-        let rs = self.client.request(&CreateDnsRecord {
+        let endpoint = CreateDnsRecord {
             zone_identifier: self.provider.zone_id.as_str(),
-            params: CreateDnsRecordParams {
-                ttl: Some(self.record.basic_record.ttl),
-                priority: None,
-                proxied: Some(self.record.proxied),
+            params: DnsRecordParams {
+                ttl: self.record.basic_record.ttl,
+                proxied: self.record.proxied,
                 name: self.record.basic_record.name.as_str(),
-                content: self.build_dns_content(dns_type)?,
+                content,
+                comment: Some(self.comment.as_str()),
             },
-        })?;
+        };
+        let rs =
+            self.retry.run("Creating the DNS record", || self.client.request(&endpoint).map_err(SyncError::from))?;
 
         Ok(rs.result)
     }
 
-    fn build_dns_content(&self, dns_type: &DnsType) -> Result<DnsContent, Error> {
+    fn apply_change(&mut self, change: PlannedChange) -> Result<SyncOutcome, SyncError> {
+        let outcome = match change.action {
+            PlannedAction::Create => {
+                let record = self.create_new_dns_record(&change.dns_type, change.desired)?;
+                self.remember(&change.dns_type, record.into(), true);
+                SyncOutcome::Created
+            }
+            PlannedAction::Update {
+                current,
+            } => {
+                let record = self.update_dns_record(&current, change.desired)?;
+                self.remember(&change.dns_type, record.into(), true);
+                SyncOutcome::Updated
+            }
+            PlannedAction::Unchanged {
+                current,
+            } => {
+                self.remember(&change.dns_type, current, false);
+                SyncOutcome::Unchanged
+            }
+            PlannedAction::Delete {
+                current,
+            } => {
+                match self.delete_dns_record(&current.id) {
+                    // Somebody else was faster, which is just as good.
+                    Ok(()) | Err(SyncError::NotFound(_)) => self.forget(&change.dns_type, &current.id),
+                    Err(err) => return Err(err),
+                }
+                SyncOutcome::Deleted
+            }
+        };
+
+        match outcome {
+            SyncOutcome::Unchanged => {
+                self.writer.debug(format!("{} record for {} is {}", change.dns_type, change.name, outcome))
+            }
+            _ => self.writer.info(format!("{} record for {} {}", change.dns_type, change.name, outcome)),
+        }
+
+        Ok(outcome)
+    }
+
+    /**
+    Called when a record we know the ID of was deleted outside of dyncloud, e.g. in the dashboard.
+    Forgets everything we know about the records of this type, looks them up again and applies the new plan.
+    Every change of the new plan gets its own outcome, like on a regular sync.
+    The new plan is not recovered again, so a record that keeps disappearing can't make us loop.
+    */
+    fn recover_missing_record(
+        &mut self,
+        dns_type: &DnsType,
+        missing: &CachedRecord,
+    ) -> Vec<Result<SyncOutcome, SyncError>> {
+        self.writer.warn(format!(
+            "{} record {} for {} no longer exists on Cloudflare. Looking it up again.",
+            dns_type, missing.id, self.record.basic_record.name
+        ));
+        self.id_cache.remove(dns_type);
+        self.state.remove(&self.state_key(dns_type));
+
+        match self.plan(dns_type) {
+            Ok(changes) => changes.into_iter().map(|change| self.apply_change(change)).collect(),
+            Err(err) => vec![Err(err)],
+        }
+    }
+
+    fn plan_records(&mut self, dns_type: &DnsType, use_cache: bool) -> Result<Vec<PlannedChange>, SyncError> {
+        self.writer.debug(format!("Planning record {} of type {}", self.record.basic_record.name, dns_type));
+
+        let desired = self.build_dns_content(dns_type)?;
+        let refresh_due = self.is_type_refresh_due(dns_type);
+        let cached = match use_cache {
+            true => self.get_cached_records(dns_type),
+            false => None,
+        };
+        let existing = match cached {
+            // The cached IDs are good enough for a write, but only the live record can tell that nothing has to change.
+            // It might have been edited in the dashboard since we cached it.
+            Some(records) if refresh_due || !records.iter().any(|record| self.is_up_to_date(record, &desired)) => {
+                records
+            }
+            _ => self.resolve_records(dns_type)?,
+        };
+        self.check_ownership(dns_type, &existing)?;
+        let (records, duplicates) = self.handle_duplicates(dns_type, existing)?;
+
+        let mut actions: Vec<PlannedAction> = records
+            .into_iter()
+            .map(|current| match self.is_up_to_date(&current, &desired) && !refresh_due {
+                true => PlannedAction::Unchanged {
+                    current,
+                },
+                false => PlannedAction::Update {
+                    current,
+                },
+            })
+            .collect();
+        if actions.is_empty() {
+            actions.push(PlannedAction::Create);
+        }
+        actions.extend(duplicates.into_iter().map(|current| PlannedAction::Delete {
+            current,
+        }));
+
+        Ok(actions
+            .into_iter()
+            .map(|action| PlannedChange {
+                name: self.record.basic_record.name.clone(),
+                dns_type: *dns_type,
+                action,
+                desired: desired.clone(),
+            })
+            .collect())
+    }
+
+    fn build_dns_content(&self, dns_type: &DnsType) -> Result<DnsContent, SyncError> {
         Ok(match dns_type {
             DnsType::A => DnsContent::A {
                 content: self.ip_resolver.get_ipv4()?,
@@ -167,32 +515,89 @@ impl CloudflareRecord {
     }
 }
 
+fn is_same_content(left: &DnsContent, right: &DnsContent) -> bool {
+    match (left, right) {
+        (
+            DnsContent::A {
+                content: left,
+            },
+            DnsContent::A {
+                content: right,
+            },
+        ) => left == right,
+        (
+            DnsContent::AAAA {
+                content: left,
+            },
+            DnsContent::AAAA {
+                content: right,
+            },
+        ) => left == right,
+        (_, _) => false,
+    }
+}
+
 impl SyncableRecord for CloudflareRecord {
-    fn sync(&mut self, progress_bar: &Option<ProgressBar>) -> Result<(), Error> {
-        let types = self.record.basic_record.dns_type.clone();
-        for dns_type in &types {
-            self.writer.debug(format!("Syncing record {} of type {}", self.record.basic_record.name, dns_type));
+    fn dns_types(&self) -> Vec<DnsType> {
+        self.record.basic_record.dns_type.clone()
+    }
 
-            self.sync_dns_record(dns_type)?;
-            if let Some(progress_bar) = progress_bar {
-                progress_bar.inc(1);
-            }
-        }
+    fn name(&self) -> &str {
+        &self.record.basic_record.name
+    }
 
-        Ok(())
+    fn is_refresh_due(&self) -> bool {
+        self.record.basic_record.dns_type.iter().any(|dns_type| self.is_type_refresh_due(dns_type))
+    }
+
+    fn plan(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+        self.plan_records(dns_type, true)
+    }
+
+    fn plan_live(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+        self.plan_records(dns_type, false)
+    }
+
+    fn apply(&mut self, change: PlannedChange) -> Vec<Result<SyncOutcome, SyncError>> {
+        let dns_type = change.dns_type;
+        let updated = match &change.action {
+            PlannedAction::Update {
+                current,
+            } => Some(current.clone()),
+            _ => None,
+        };
+
+        match (self.apply_change(change), updated) {
+            (Err(SyncError::NotFound(_)), Some(missing)) => self.recover_missing_record(&dns_type, &missing),
+            (result, _) => vec![result],
+        }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use crate::Verbosity;
+    use crate::cloudflare_api::CloudflareClient;
+    use crate::cloudflare_api::dns::ownership_comment;
     use crate::configuration::user::providers::Cloudflare;
-    use crate::configuration::user::records::{BasicRecord, CloudflareRecord as CloudflareConfigRecord, DnsType};
-    use crate::dns::record::{CloudflareRecord, SyncableRecord};
+    use crate::configuration::user::records::{
+        BasicRecord, CloudflareRecord as CloudflareConfigRecord, DnsType, OnDuplicate,
+    };
+    use crate::configuration::user::retry::RetryConfig;
+    use crate::dns::error::SyncError;
+    use crate::dns::plan::{PlannedAction, format_dns_content};
+    use crate::dns::record::{CachedRecord, CloudflareRecord, SyncableRecord};
+    use crate::dns::report::SyncOutcome;
     use crate::io_helper::CliWriter;
     use crate::ip::cache::IpCache;
     use crate::ip::resolver::IpResolver;
-    use crate::ip::resolver::ipify::IpifyResolver;
+    use crate::ip::resolver::http::{HttpEndpoint, HttpResolver, ResponseFormat};
+    use crate::rate_limit::RateLimiter;
+    use crate::retry::RetryPolicy;
+    use crate::state::store::{StateStore, cloudflare_state_key};
+    use chrono::Utc;
+    use cloudflare::endpoints::dns::dns::DnsContent;
     use cloudflare::framework::Environment;
     use cloudflare::framework::auth::Credentials;
     use cloudflare::framework::client::ClientConfig;
@@ -201,6 +606,7 @@ mod tests {
     use reqwest::blocking::Client;
     use serde_json::json;
     use std::sync::Arc;
+    use std::time::Duration;
 
     #[test]
     fn test_sync_record() {
@@ -278,7 +684,7 @@ mod tests {
                 .create(),
         );
 
-        let http_client = Arc::new(
+        let http_client = Arc::new(CloudflareClient::new(
             HttpApiClient::new(
                 Credentials::UserAuthToken {
                     token: "CustomAuthToken".to_string(),
@@ -287,31 +693,620 @@ mod tests {
                 Environment::Custom(server.url()),
             )
             .unwrap(),
-        );
+            RateLimiter::new(100.0),
+        ));
         let mut record = CloudflareRecord {
             ip_resolver: ip_res.clone(),
             client: http_client.clone(),
             provider: Arc::new(Cloudflare {
                 auth_token: "CustomAuthToken".to_string(),
                 zone_id: "ZoneID".to_string(),
+                requests_per_second: 4.0,
             }),
             record: CloudflareConfigRecord {
                 basic_record: BasicRecord {
                     name: "test.example.internal".to_string(),
                     ttl: 60,
                     dns_type: vec![DnsType::A],
+                    cron: None,
+                    force_update_after: None,
                 },
                 proxied: false,
+                on_duplicate: Default::default(),
+                require_ownership: false,
             },
             writer: Arc::new(CliWriter::new(&Verbosity::default())),
+            state: Arc::new(StateStore::in_memory()),
+            retry: RetryPolicy::from(&RetryConfig::default()),
+            comment: ownership_comment(None),
             id_cache: Default::default(),
         };
 
-        assert!(record.sync(&None).is_ok());
+        assert!(record.sync(&None).iter().all(|report| report.result.is_ok()));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_sync_record_skips_update_when_unchanged() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_list_response("127.0.0.1"))
+                .expect(2)
+                .create(),
+        );
+        mocks.push(server.mock("PUT", Matcher::Any).expect(0).create());
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+
+        // Both syncs check the live record, but neither of them writes.
+        assert!(record.sync(&None).iter().all(|report| report.result.is_ok()));
+        assert!(record.sync(&None).iter().all(|report| report.result.is_ok()));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_sync_record_rechecks_a_cached_record_before_skipping() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        // Somebody changed the record in the dashboard after we cached it.
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_list_response("192.168.0.1"))
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/2eef68ee36ba268bb9aa3593e3ff7dc3")
+                .match_body(Matcher::PartialJson(json!({"content": "127.0.0.1"})))
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        state.set(
+            cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A),
+            vec![create_cached_record("127.0.0.1")],
+        );
+        let mut record = create_test_record(ip_res, &server, state);
+
+        let reports = record.sync(&None);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Updated)));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_sync_record_uses_id_from_state() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(server.mock("GET", Matcher::Any).expect(0).create());
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/2eef68ee36ba268bb9aa3593e3ff7dc3")
+                .match_body(Matcher::PartialJson(json!({"content": "127.0.0.1"})))
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        let key = cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A);
+        state.set(
+            key.clone(),
+            vec![CachedRecord {
+                id: "2eef68ee36ba268bb9aa3593e3ff7dc3".to_string(),
+                content: DnsContent::A {
+                    content: "192.168.0.1".parse().unwrap(),
+                },
+                ttl: 60,
+                proxied: false,
+                comment: None,
+            }],
+        );
+
+        let mut record = create_test_record(ip_res, &server, state.clone());
+
+        assert!(record.sync(&None).iter().all(|report| report.result.is_ok()));
+        assert!(matches!(
+            state.get(&key).unwrap().records[0].content,
+            DnsContent::A { content } if content.to_string() == "127.0.0.1"
+        ));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_sync_record_forces_update_after_duration() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/2eef68ee36ba268bb9aa3593e3ff7dc3")
+                .match_body(Matcher::PartialJson(json!({"content": "127.0.0.1"})))
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .expect(1)
+                .create(),
+        );
+        // Only the second sync, the refresh writes with the cached ID.
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_list_response("127.0.0.1"))
+                .expect(1)
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        let key = cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A);
+        state.set_written(
+            key.clone(),
+            vec![create_cached_record("127.0.0.1")],
+            Utc::now() - chrono::Duration::days(31),
+        );
+
+        let mut record = create_test_record(ip_res, &server, state.clone());
+        record.record.basic_record.force_update_after = Some(Duration::from_secs(30 * 24 * 60 * 60));
+
+        assert!(record.is_refresh_due());
+        let reports = record.sync(&None);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Updated)));
+
+        // The write resets the clock, so the next check leaves the record alone.
+        assert!(!record.is_refresh_due());
+        let reports = record.sync(&None);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Unchanged)));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_sync_record_recovers_from_externally_deleted_record() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/deleted")
+                .with_status(404)
+                .with_body(json!({"result": null, "success": false, "errors": [], "messages": []}).to_string())
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(json!({"result": [], "success": true, "errors": [], "messages": []}).to_string())
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("POST", "/zones/ZoneID/dns_records")
+                .match_body(Matcher::PartialJson(json!({"content": "127.0.0.1", "comment": "managed-by=dyncloud"})))
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        let key = cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A);
+        state.set(
+            key.clone(),
+            vec![CachedRecord {
+                id: "deleted".to_string(),
+                content: DnsContent::A {
+                    content: "192.168.0.1".parse().unwrap(),
+                },
+                ttl: 60,
+                proxied: false,
+                comment: None,
+            }],
+        );
+
+        let mut record = create_test_record(ip_res, &server, state.clone());
+        let reports = record.sync(&None);
+
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Created)));
+        assert_eq!(state.get(&key).unwrap().records[0].id, "2eef68ee36ba268bb9aa3593e3ff7dc3");
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_recovery_reports_every_change_of_the_new_plan() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/deleted")
+                .with_status(404)
+                .with_body(json!({"result": null, "success": false, "errors": [], "messages": []}).to_string())
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_duplicates_response())
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/first")
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("DELETE", "/zones/ZoneID/dns_records/second")
+                .with_status(403)
+                .with_body(json!({"result": null, "success": false, "errors": [], "messages": []}).to_string())
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        state.set(
+            cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A),
+            vec![CachedRecord {
+                id: "deleted".to_string(),
+                ..create_cached_record("192.168.0.1")
+            }],
+        );
+
+        let mut record = create_test_record(ip_res, &server, state);
+        record.record.on_duplicate = OnDuplicate::KeepFirst;
+        let reports = record.sync(&None);
+
+        // The failing deletion of the duplicate must not hide the update that went through.
+        assert_eq!(reports.len(), 2);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Updated)));
+        assert!(reports[1].result.is_err());
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_require_ownership_leaves_foreign_records_alone() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_list_response("192.168.0.1"))
+                .create(),
+        );
+        mocks.push(server.mock("PUT", Matcher::Any).expect(0).create());
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+        record.record.require_ownership = true;
+
+        let reports = record.sync(&None);
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].result, Err(SyncError::Conflict(_))));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_plan_does_not_change_records() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_list_response("192.168.0.1"))
+                .create(),
+        );
+        mocks.push(server.mock("PUT", Matcher::Any).expect(0).create());
+        mocks.push(server.mock("POST", Matcher::Any).expect(0).create());
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+        let changes = record.plan(&DnsType::A).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(changes[0].action, PlannedAction::Update { .. }));
+        assert_eq!(format_dns_content(&changes[0].desired), "127.0.0.1");
 
         mocks.iter().for_each(|mock| mock.assert());
     }
 
+    #[test]
+    fn test_plan_live_ignores_the_state() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        // The record was deleted in the dashboard, but the state file still knows it.
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(json!({"result": [], "success": true, "errors": [], "messages": []}).to_string())
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        state.set(
+            cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A),
+            vec![create_cached_record("192.168.0.1")],
+        );
+        let mut record = create_test_record(ip_res, &server, state);
+        let changes = record.plan_live(&DnsType::A).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(changes[0].action, PlannedAction::Create));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_duplicates_fail_by_default() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_duplicates_response())
+                .create(),
+        );
+        mocks.push(server.mock("PUT", Matcher::Any).expect(0).create());
+        mocks.push(server.mock("DELETE", Matcher::Any).expect(0).create());
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+
+        assert!(matches!(record.plan(&DnsType::A), Err(SyncError::Conflict(_))));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_duplicates_update_all() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_duplicates_response())
+                .create(),
+        );
+        for id in ["first", "second"] {
+            mocks.push(
+                server
+                    .mock("PUT", format!("/zones/ZoneID/dns_records/{}", id).as_str())
+                    .with_status(200)
+                    .with_body(create_record_response("127.0.0.1"))
+                    .create(),
+            );
+        }
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+        record.record.on_duplicate = OnDuplicate::UpdateAll;
+
+        let reports = record.sync(&None);
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|report| matches!(report.result, Ok(SyncOutcome::Updated))));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_duplicates_keep_first() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_duplicates_response())
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/first")
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+        mocks.push(
+            server
+                .mock("DELETE", "/zones/ZoneID/dns_records/second")
+                .with_status(200)
+                .with_body(
+                    json!({"result": {"id": "second"}, "success": true, "errors": [], "messages": []}).to_string(),
+                )
+                .create(),
+        );
+
+        let state = Arc::new(StateStore::in_memory());
+        let mut record = create_test_record(ip_res, &server, state.clone());
+        record.record.on_duplicate = OnDuplicate::KeepFirst;
+
+        let outcomes: Vec<SyncOutcome> = record.sync(&None).into_iter().map(|report| report.result.unwrap()).collect();
+        assert_eq!(outcomes, vec![SyncOutcome::Updated, SyncOutcome::Deleted]);
+
+        let key = cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A);
+        assert_eq!(state.get(&key).unwrap().records.len(), 1);
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_duplicates_update_owned() {
+        let (ip_res, mut mocks, _server_guard) = get_mock_ip_resolver();
+        let mut server = Server::new();
+
+        mocks.push(
+            server
+                .mock("GET", "/zones/ZoneID/dns_records?name=test.example.internal")
+                .with_status(200)
+                .with_body(create_duplicates_response())
+                .create(),
+        );
+        mocks.push(server.mock("PUT", "/zones/ZoneID/dns_records/first").expect(0).create());
+        mocks.push(
+            server
+                .mock("PUT", "/zones/ZoneID/dns_records/second")
+                .match_body(Matcher::PartialJson(json!({"comment": "managed-by=dyncloud"})))
+                .with_status(200)
+                .with_body(create_record_response("127.0.0.1"))
+                .create(),
+        );
+
+        let mut record = create_test_record(ip_res, &server, Arc::new(StateStore::in_memory()));
+        record.record.on_duplicate = OnDuplicate::UpdateOwned;
+
+        let reports = record.sync(&None);
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].result, Ok(SyncOutcome::Updated)));
+
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    fn create_test_record(
+        ip_resolver: Arc<Box<dyn IpResolver>>,
+        server: &ServerGuard,
+        state: Arc<StateStore>,
+    ) -> CloudflareRecord {
+        let http_client = HttpApiClient::new(
+            Credentials::UserAuthToken {
+                token: "CustomAuthToken".to_string(),
+            },
+            ClientConfig::default(),
+            Environment::Custom(server.url()),
+        )
+        .unwrap();
+
+        CloudflareRecord::new(
+            ip_resolver,
+            Arc::new(CloudflareClient::new(http_client, RateLimiter::new(100.0))),
+            Arc::new(Cloudflare {
+                auth_token: "CustomAuthToken".to_string(),
+                zone_id: "ZoneID".to_string(),
+                requests_per_second: 4.0,
+            }),
+            CloudflareConfigRecord {
+                basic_record: BasicRecord {
+                    name: "test.example.internal".to_string(),
+                    ttl: 60,
+                    dns_type: vec![DnsType::A],
+                    cron: None,
+                    force_update_after: None,
+                },
+                proxied: false,
+                on_duplicate: Default::default(),
+                require_ownership: false,
+            },
+            Arc::new(CliWriter::new(&Verbosity::default())),
+            state,
+            RetryPolicy::from(&RetryConfig::default()),
+        )
+    }
+
+    fn create_cached_record(content: &str) -> CachedRecord {
+        CachedRecord {
+            id: "2eef68ee36ba268bb9aa3593e3ff7dc3".to_string(),
+            content: DnsContent::A {
+                content: content.parse().unwrap(),
+            },
+            ttl: 60,
+            proxied: false,
+            comment: None,
+        }
+    }
+
+    fn create_dns_record_json(content: &str) -> serde_json::Value {
+        json!({
+          "id": "2eef68ee36ba268bb9aa3593e3ff7dc3",
+          "name": "test.example.internal",
+          "type": "A",
+          "content": content,
+          "proxiable": true,
+          "proxied": false,
+          "ttl": 60,
+          "settings": {},
+          "meta": {},
+          "comment": null,
+          "tags": [],
+          "created_on": "2024-12-27T16:33:47.054786Z",
+          "modified_on": "2025-10-08T20:48:29.15931Z"
+        })
+    }
+
+    fn create_list_response(content: &str) -> String {
+        json!({
+          "result": [create_dns_record_json(content)],
+          "success": true,
+          "errors": [],
+          "messages": [],
+          "result_info": {
+            "page": 1,
+            "per_page": 100,
+            "count": 1,
+            "total_count": 1,
+            "total_pages": 1
+          }
+        })
+        .to_string()
+    }
+
+    fn create_duplicates_response() -> String {
+        let mut first = create_dns_record_json("192.168.0.1");
+        first["id"] = json!("first");
+        let mut second = create_dns_record_json("192.168.0.2");
+        second["id"] = json!("second");
+        second["comment"] = json!("managed-by=dyncloud");
+
+        json!({
+          "result": [first, second],
+          "success": true,
+          "errors": [],
+          "messages": [],
+          "result_info": {
+            "page": 1,
+            "per_page": 100,
+            "count": 2,
+            "total_count": 2,
+            "total_pages": 1
+          }
+        })
+        .to_string()
+    }
+
+    fn create_record_response(content: &str) -> String {
+        json!({
+          "result": create_dns_record_json(content),
+          "success": true,
+          "errors": [],
+          "messages": []
+        })
+        .to_string()
+    }
+
     fn get_mock_ip_resolver() -> (Arc<Box<dyn IpResolver>>, Vec<Mock>, ServerGuard) {
         let mut server = Server::new();
         let mut mocks = Vec::new();
@@ -326,11 +1321,11 @@ mod tests {
         );
 
         (
-            Arc::new(Box::new(IpifyResolver::new(
+            Arc::new(Box::new(HttpResolver::new(
+                "test",
                 Arc::new(IpCache::new(10)),
-                Client::new(),
-                format!("{}/ipv4", server.url().as_str()),
-                format!("{}/ipv6", server.url().as_str()),
+                Some(HttpEndpoint::new(&format!("{}/ipv4", server.url()), ResponseFormat::PlainText, Client::new())),
+                Some(HttpEndpoint::new(&format!("{}/ipv6", server.url()), ResponseFormat::PlainText, Client::new())),
             ))),
             mocks,
             server,
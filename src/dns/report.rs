@@ -0,0 +1,119 @@
+use crate::configuration::user::records::DnsType;
+use crate::dns::error::SyncError;
+use std::fmt::Display;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SyncOutcome {
+    Created,
+    Updated,
+    Deleted,
+    Unchanged,
+}
+
+impl Display for SyncOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncOutcome::Created => write!(f, "created"),
+            SyncOutcome::Updated => write!(f, "updated"),
+            SyncOutcome::Deleted => write!(f, "deleted"),
+            SyncOutcome::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/**
+The result of syncing a single record of a single type.
+*/
+#[derive(Debug)]
+pub(crate) struct RecordReport {
+    pub(crate) name: String,
+    pub(crate) dns_type: DnsType,
+    pub(crate) result: Result<SyncOutcome, SyncError>,
+}
+
+/**
+Collects the results of all records of a sync, so a single failing record does not hide the others.
+*/
+#[derive(Debug, Default)]
+pub(crate) struct SyncReport {
+    pub(crate) records: Vec<RecordReport>,
+}
+
+impl SyncReport {
+    pub(crate) fn extend(&mut self, records: Vec<RecordReport>) {
+        self.records.extend(records);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub(crate) fn count(&self, outcome: SyncOutcome) -> usize {
+        self.records.iter().filter(|record| matches!(record.result, Ok(result) if result == outcome)).count()
+    }
+
+    pub(crate) fn failures(&self) -> impl Iterator<Item = &RecordReport> {
+        self.records.iter().filter(|record| record.result.is_err())
+    }
+
+    pub(crate) fn failed(&self) -> usize {
+        self.failures().count()
+    }
+
+    pub(crate) fn has_failures(&self) -> bool {
+        self.failures().next().is_some()
+    }
+}
+
+impl Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} created, {} updated, {} deleted, {} unchanged, {} failed",
+            self.count(SyncOutcome::Created),
+            self.count(SyncOutcome::Updated),
+            self.count(SyncOutcome::Deleted),
+            self.count(SyncOutcome::Unchanged),
+            self.failed()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::user::records::DnsType;
+    use crate::dns::error::SyncError;
+    use crate::dns::report::{RecordReport, SyncOutcome, SyncReport};
+
+    fn create_report(dns_type: DnsType, result: Result<SyncOutcome, SyncError>) -> RecordReport {
+        RecordReport {
+            name: "test.example.internal".to_string(),
+            dns_type,
+            result,
+        }
+    }
+
+    #[test]
+    fn test_empty_report_has_no_failures() {
+        let report = SyncReport::default();
+
+        assert!(!report.has_failures());
+        assert_eq!(report.to_string(), "0 created, 0 updated, 0 deleted, 0 unchanged, 0 failed");
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let mut report = SyncReport::default();
+        report.extend(vec![
+            create_report(DnsType::A, Ok(SyncOutcome::Updated)),
+            create_report(DnsType::Aaaa, Err(SyncError::Transport("Connection refused".to_string()))),
+            create_report(DnsType::A, Ok(SyncOutcome::Unchanged)),
+            create_report(DnsType::A, Ok(SyncOutcome::Unchanged)),
+        ]);
+
+        assert!(report.has_failures());
+        assert_eq!(report.len(), 4);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.to_string(), "0 created, 1 updated, 0 deleted, 2 unchanged, 1 failed");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::commands::cloudflare::CloudflareCommandError;
 use crate::configuration::user::error::ConfigError;
+use crate::dns::error::SyncError;
+use crate::state::error::StateError;
 use thiserror::Error;
 use tracing::error;
 use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
@@ -12,6 +14,32 @@ pub(crate) enum ApplicationError {
     ConfigError(#[from] ConfigError),
     #[error(transparent)]
     ValidationErrors(#[from] ValidationErrors),
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error(transparent)]
+    Sync(#[from] SyncError),
+    #[error(transparent)]
+    Run(#[from] anyhow::Error),
+    #[error("Failed to sync {failed} of {total} DNS records")]
+    SyncFailed {
+        failed: usize,
+        total: usize,
+    },
+}
+
+impl ApplicationError {
+    /**
+    The exit code of the process, so scripts can tell a broken config apart from records that failed to sync.
+    */
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            ApplicationError::ConfigError(_) | ApplicationError::ValidationErrors(_) => 2,
+            ApplicationError::SyncFailed {
+                ..
+            } => 3,
+            _ => 1,
+        }
+    }
 }
 
 pub(crate) fn print_validation_errors(errors: &ValidationErrors) {
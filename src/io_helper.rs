@@ -6,7 +6,7 @@ use clap_verbosity_flag::VerbosityFilter;
 const DEBUG_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Blue)));
 const INFO_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Green)));
 const SUCCESS_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Cyan))).bold();
-// const WARN_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Yellow))).bold();
+const WARN_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Yellow))).bold();
 const ERROR_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Red))).bold();
 
 /**
@@ -40,9 +40,9 @@ impl CliWriter {
         self.write(message, &VerbosityFilter::Info, &SUCCESS_STYLE);
     }
 
-    // pub(crate) fn warn(&self, message: impl AsRef<str>) {
-    //     self.write(message, &VerbosityFilter::Warn, &WARN_STYLE);
-    // }
+    pub(crate) fn warn(&self, message: impl AsRef<str>) {
+        self.write(message, &VerbosityFilter::Warn, &WARN_STYLE);
+    }
 
     pub(crate) fn error(&self, message: impl AsRef<str>) {
         self.write(message, &VerbosityFilter::Error, &ERROR_STYLE);
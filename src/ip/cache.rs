@@ -2,6 +2,33 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::RwLock;
 use std::time::Instant;
 
+/**
+How long an address has been observed without changing.
+*/
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Observation {
+    pub(crate) first_seen: Instant,
+    /**
+    The number of consecutive lookups that returned this address.
+    */
+    pub(crate) checks: u32,
+}
+
+impl Observation {
+    fn next(previous: Option<Observation>, same_address: bool) -> Self {
+        match previous {
+            Some(previous) if same_address => Self {
+                first_seen: previous.first_seen,
+                checks: previous.checks.saturating_add(1),
+            },
+            _ => Self {
+                first_seen: Instant::now(),
+                checks: 1,
+            },
+        }
+    }
+}
+
 pub(crate) struct IpCache {
     ttl: u64,
     inner: RwLock<IpCacheInner>,
@@ -15,9 +42,14 @@ impl IpCache {
         }
     }
 
+    pub(crate) fn ttl(&self) -> u64 {
+        self.ttl
+    }
+
     pub(crate) fn set_ipv4addr(&self, ipv4addr: Ipv4Addr) {
         #[allow(clippy::expect_used)]
         let mut inner = self.inner.write().expect("IP cache poisoned");
+        inner.ipv4_observation = Some(Observation::next(inner.ipv4_observation, inner.ipv4addr == Some(ipv4addr)));
         inner.ipv4addr = Some(ipv4addr);
         inner.ipv4_last_update = Some(Instant::now());
     }
@@ -25,10 +57,50 @@ impl IpCache {
     pub(crate) fn set_ipv6addr(&self, ipv6addr: Ipv6Addr) {
         #[allow(clippy::expect_used)]
         let mut inner = self.inner.write().expect("IP cache poisoned");
+        inner.ipv6_observation = Some(Observation::next(inner.ipv6_observation, inner.ipv6addr == Some(ipv6addr)));
         inner.ipv6addr = Some(ipv6addr);
         inner.ipv6_last_update = Some(Instant::now());
     }
 
+    /**
+    Expires the cached addresses, so the next lookup has to resolve them again.
+    */
+    pub(crate) fn invalidate(&self) {
+        #[allow(clippy::expect_used)]
+        let mut inner = self.inner.write().expect("IP cache poisoned");
+        inner.ipv4_last_update = None;
+        inner.ipv6_last_update = None;
+    }
+
+    /**
+    Copies the addresses and their observations of another cache, e.g. the one of the same group before a reload.
+    */
+    pub(crate) fn take_over(&self, other: &IpCache) {
+        #[allow(clippy::expect_used)]
+        let other = other.inner.read().expect("IP cache poisoned").clone();
+        #[allow(clippy::expect_used)]
+        let mut inner = self.inner.write().expect("IP cache poisoned");
+        *inner = other;
+    }
+
+    /**
+    Since when and how often the current IPv4 address was looked up. Survives [`IpCache::invalidate`].
+    */
+    pub(crate) fn ipv4_observation(&self) -> Option<Observation> {
+        #[allow(clippy::expect_used)]
+        let inner = self.inner.read().expect("IP cache poisoned");
+        inner.ipv4_observation
+    }
+
+    /**
+    Since when and how often the current IPv6 address was looked up. Survives [`IpCache::invalidate`].
+    */
+    pub(crate) fn ipv6_observation(&self) -> Option<Observation> {
+        #[allow(clippy::expect_used)]
+        let inner = self.inner.read().expect("IP cache poisoned");
+        inner.ipv6_observation
+    }
+
     pub(crate) fn get_ipv4addr(&self) -> Option<Ipv4Addr> {
         #[allow(clippy::expect_used)]
         let inner = self.inner.read().expect("IP cache poisoned");
@@ -60,15 +132,18 @@ impl IpCache {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct IpCacheInner {
     ipv4_last_update: Option<Instant>,
     ipv4addr: Option<Ipv4Addr>,
+    ipv4_observation: Option<Observation>,
     ipv6_last_update: Option<Instant>,
     ipv6addr: Option<Ipv6Addr>,
+    ipv6_observation: Option<Observation>,
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
     use std::thread;
@@ -149,6 +224,45 @@ mod tests {
         assert_eq!(cache.get_ipv6addr(), Some(ipv6));
     }
 
+    #[test]
+    fn test_invalidate_expires_addresses() {
+        let cache = IpCache::new(60);
+        cache.set_ipv4addr("10.0.0.1".parse::<Ipv4Addr>().unwrap());
+        cache.set_ipv6addr("fe80::1".parse::<Ipv6Addr>().unwrap());
+
+        cache.invalidate();
+
+        assert_eq!(cache.get_ipv4addr(), None);
+        assert_eq!(cache.get_ipv6addr(), None);
+    }
+
+    #[test]
+    fn test_observation_counts_consecutive_lookups() {
+        let cache = IpCache::new(60);
+        let ipv4 = "10.0.0.1".parse::<Ipv4Addr>().unwrap();
+
+        cache.set_ipv4addr(ipv4);
+        let first = cache.ipv4_observation().unwrap();
+        cache.invalidate();
+        cache.set_ipv4addr(ipv4);
+        let second = cache.ipv4_observation().unwrap();
+
+        assert_eq!(first.checks, 1);
+        assert_eq!(second.checks, 2);
+        assert_eq!(second.first_seen, first.first_seen);
+    }
+
+    #[test]
+    fn test_observation_restarts_on_new_address() {
+        let cache = IpCache::new(60);
+
+        cache.set_ipv6addr("2001:db8::1".parse::<Ipv6Addr>().unwrap());
+        cache.set_ipv6addr("2001:db8::1".parse::<Ipv6Addr>().unwrap());
+        cache.set_ipv6addr("2001:db8::2".parse::<Ipv6Addr>().unwrap());
+
+        assert_eq!(cache.ipv6_observation().unwrap().checks, 1);
+    }
+
     #[test]
     fn test_updating_address_resets_ttl() {
         let cache = IpCache::new(2);
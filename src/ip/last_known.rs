@@ -0,0 +1,89 @@
+use std::fmt::Display;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/**
+The public addresses that were observed during a single check.
+An address is `None` if none of the records needs it.
+*/
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(crate) struct IpSnapshot {
+    pub(crate) ipv4: Option<Ipv4Addr>,
+    pub(crate) ipv6: Option<Ipv6Addr>,
+}
+
+impl Display for IpSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.ipv4, self.ipv6) {
+            (Some(ipv4), Some(ipv6)) => write!(f, "{} / {}", ipv4, ipv6),
+            (Some(ipv4), None) => write!(f, "{}", ipv4),
+            (None, Some(ipv6)) => write!(f, "{}", ipv6),
+            (None, None) => write!(f, "-"),
+        }
+    }
+}
+
+/**
+Remembers the addresses that were last pushed successfully, so records are only touched when the public IP changes.
+*/
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LastKnownIp {
+    snapshot: Option<IpSnapshot>,
+}
+
+impl LastKnownIp {
+    pub(crate) fn has_changed(&self, observed: &IpSnapshot) -> bool {
+        self.snapshot.as_ref() != Some(observed)
+    }
+
+    pub(crate) fn snapshot(&self) -> Option<&IpSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    pub(crate) fn remember(&mut self, observed: IpSnapshot) {
+        self.snapshot = Some(observed);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::last_known::{IpSnapshot, LastKnownIp};
+
+    #[test]
+    fn test_first_observation_is_a_change() {
+        let last_known = LastKnownIp::default();
+        let observed = IpSnapshot {
+            ipv4: Some("192.168.1.1".parse().unwrap()),
+            ipv6: None,
+        };
+
+        assert!(last_known.has_changed(&observed));
+    }
+
+    #[test]
+    fn test_same_observation_is_not_a_change() {
+        let mut last_known = LastKnownIp::default();
+        let observed = IpSnapshot {
+            ipv4: Some("192.168.1.1".parse().unwrap()),
+            ipv6: Some("2001:db8::1".parse().unwrap()),
+        };
+
+        last_known.remember(observed);
+        assert!(!last_known.has_changed(&observed));
+    }
+
+    #[test]
+    fn test_ipv6_change_is_a_change() {
+        let mut last_known = LastKnownIp::default();
+        let ipv4 = Some("192.168.1.1".parse().unwrap());
+
+        last_known.remember(IpSnapshot {
+            ipv4,
+            ipv6: Some("2001:db8::1".parse().unwrap()),
+        });
+        assert!(last_known.has_changed(&IpSnapshot {
+            ipv4,
+            ipv6: Some("2001:db8::2".parse().unwrap()),
+        }));
+    }
+}
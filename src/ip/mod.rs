@@ -1,2 +1,4 @@
 pub(crate) mod cache;
+pub(crate) mod last_known;
 pub(crate) mod resolver;
+pub(crate) mod stabilization;
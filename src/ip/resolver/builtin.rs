@@ -0,0 +1,224 @@
+use crate::ip::cache::IpCache;
+use crate::ip::resolver::http::{HttpEndpoint, HttpResolver, ResponseFormat, ipv4_client, ipv6_client};
+use reqwest::blocking::Client;
+use std::sync::Arc;
+
+const IPIFY_V4_URL: &str = "https://api.ipify.org";
+const IPIFY_V6_URL: &str = "https://api6.ipify.org";
+const ICANHAZIP_V4_URL: &str = "https://ipv4.icanhazip.com";
+const ICANHAZIP_V6_URL: &str = "https://ipv6.icanhazip.com";
+const IFCONFIG_CO_URL: &str = "https://ifconfig.co/json";
+const IFCONFIG_ME_URL: &str = "https://ifconfig.me/ip";
+const IPINFO_V4_URL: &str = "https://ipinfo.io/json";
+const IPINFO_V6_URL: &str = "https://v6.ipinfo.io/json";
+const AWS_CHECKIP_URL: &str = "https://checkip.amazonaws.com";
+const CLOUDFLARE_TRACE_URL: &str = "https://www.cloudflare.com/cdn-cgi/trace";
+
+/**
+The built-in lookup services.
+Services without separate hosts per IP family get a client that is pinned to the family instead.
+*/
+impl HttpResolver {
+    pub(crate) fn ipify(cache: Arc<IpCache>) -> Self {
+        Self::new(
+            "ipify",
+            cache,
+            Some(HttpEndpoint::new(IPIFY_V4_URL, ResponseFormat::PlainText, Client::new())),
+            Some(HttpEndpoint::new(IPIFY_V6_URL, ResponseFormat::PlainText, Client::new())),
+        )
+    }
+
+    pub(crate) fn icanhazip(cache: Arc<IpCache>) -> Self {
+        Self::new(
+            "icanhazip",
+            cache,
+            Some(HttpEndpoint::new(ICANHAZIP_V4_URL, ResponseFormat::PlainText, ipv4_client())),
+            Some(HttpEndpoint::new(ICANHAZIP_V6_URL, ResponseFormat::PlainText, ipv6_client())),
+        )
+    }
+
+    pub(crate) fn ifconfig_co(cache: Arc<IpCache>) -> Self {
+        let format = ResponseFormat::JsonPointer("/ip".to_string());
+
+        Self::new(
+            "ifconfig.co",
+            cache,
+            Some(HttpEndpoint::new(IFCONFIG_CO_URL, format.clone(), ipv4_client())),
+            Some(HttpEndpoint::new(IFCONFIG_CO_URL, format, ipv6_client())),
+        )
+    }
+
+    pub(crate) fn ifconfig_me(cache: Arc<IpCache>) -> Self {
+        Self::new(
+            "ifconfig.me",
+            cache,
+            Some(HttpEndpoint::new(IFCONFIG_ME_URL, ResponseFormat::PlainText, ipv4_client())),
+            Some(HttpEndpoint::new(IFCONFIG_ME_URL, ResponseFormat::PlainText, ipv6_client())),
+        )
+    }
+
+    pub(crate) fn ipinfo(cache: Arc<IpCache>) -> Self {
+        let format = ResponseFormat::JsonPointer("/ip".to_string());
+
+        Self::new(
+            "ipinfo",
+            cache,
+            Some(HttpEndpoint::new(IPINFO_V4_URL, format.clone(), ipv4_client())),
+            Some(HttpEndpoint::new(IPINFO_V6_URL, format, ipv6_client())),
+        )
+    }
+
+    /**
+    AWS only answers on IPv4.
+    */
+    pub(crate) fn aws_checkip(cache: Arc<IpCache>) -> Self {
+        Self::new(
+            "AWS checkip",
+            cache,
+            Some(HttpEndpoint::new(AWS_CHECKIP_URL, ResponseFormat::PlainText, ipv4_client())),
+            None,
+        )
+    }
+
+    pub(crate) fn cloudflare_trace(cache: Arc<IpCache>) -> Self {
+        let format = ResponseFormat::KeyValue("ip".to_string());
+
+        Self::new(
+            "Cloudflare trace",
+            cache,
+            Some(HttpEndpoint::new(CLOUDFLARE_TRACE_URL, format.clone(), ipv4_client())),
+            Some(HttpEndpoint::new(CLOUDFLARE_TRACE_URL, format, ipv6_client())),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::cache::IpCache;
+    use crate::ip::resolver::IpResolver;
+    use crate::ip::resolver::error::ResolverError;
+    use crate::ip::resolver::http::HttpResolver;
+    use mockito::Server;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::Arc;
+
+    const IPV4: &str = "203.0.113.1";
+    const IPV6: &str = "2001:db8::1";
+
+    fn cache() -> Arc<IpCache> {
+        Arc::new(IpCache::new(60))
+    }
+
+    fn lookup_ipv4(resolver: HttpResolver, path: &str, body: &str) -> Result<Ipv4Addr, ResolverError> {
+        let mut server = Server::new();
+        let mock = server.mock("GET", path).with_status(200).with_body(body).create();
+
+        let ip = resolver.on_server(&server.url()).get_ipv4();
+        mock.assert();
+        ip
+    }
+
+    fn lookup_ipv6(resolver: HttpResolver, path: &str, body: &str) -> Result<Ipv6Addr, ResolverError> {
+        let mut server = Server::new();
+        let mock = server.mock("GET", path).with_status(200).with_body(body).create();
+
+        let ip = resolver.on_server(&server.url()).get_ipv6();
+        mock.assert();
+        ip
+    }
+
+    #[test]
+    fn test_ipify() {
+        assert_eq!(
+            HttpResolver::ipify(cache()).urls(),
+            (Some("https://api.ipify.org"), Some("https://api6.ipify.org"))
+        );
+        assert_eq!(lookup_ipv4(HttpResolver::ipify(cache()), "/", IPV4).unwrap().to_string(), IPV4);
+        assert_eq!(lookup_ipv6(HttpResolver::ipify(cache()), "/", IPV6).unwrap().to_string(), IPV6);
+    }
+
+    #[test]
+    fn test_icanhazip() {
+        assert_eq!(
+            HttpResolver::icanhazip(cache()).urls(),
+            (Some("https://ipv4.icanhazip.com"), Some("https://ipv6.icanhazip.com"))
+        );
+        assert_eq!(
+            lookup_ipv4(HttpResolver::icanhazip(cache()), "/", &format!("{}\n", IPV4)).unwrap().to_string(),
+            IPV4
+        );
+        assert_eq!(
+            lookup_ipv6(HttpResolver::icanhazip(cache()), "/", &format!("{}\n", IPV6)).unwrap().to_string(),
+            IPV6
+        );
+    }
+
+    #[test]
+    fn test_ifconfig_co() {
+        let body = |ip: &str| format!(r#"{{"ip": "{}", "country": "Germany", "asn": "AS64496"}}"#, ip);
+
+        assert_eq!(
+            HttpResolver::ifconfig_co(cache()).urls(),
+            (Some("https://ifconfig.co/json"), Some("https://ifconfig.co/json"))
+        );
+        assert_eq!(lookup_ipv4(HttpResolver::ifconfig_co(cache()), "/json", &body(IPV4)).unwrap().to_string(), IPV4);
+        assert_eq!(lookup_ipv6(HttpResolver::ifconfig_co(cache()), "/json", &body(IPV6)).unwrap().to_string(), IPV6);
+    }
+
+    #[test]
+    fn test_ifconfig_me() {
+        assert_eq!(
+            HttpResolver::ifconfig_me(cache()).urls(),
+            (Some("https://ifconfig.me/ip"), Some("https://ifconfig.me/ip"))
+        );
+        assert_eq!(lookup_ipv4(HttpResolver::ifconfig_me(cache()), "/ip", IPV4).unwrap().to_string(), IPV4);
+        assert_eq!(lookup_ipv6(HttpResolver::ifconfig_me(cache()), "/ip", IPV6).unwrap().to_string(), IPV6);
+    }
+
+    #[test]
+    fn test_ipinfo() {
+        let body = |ip: &str| format!(r#"{{"ip": "{}", "city": "Berlin", "org": "AS64496 Example"}}"#, ip);
+
+        assert_eq!(
+            HttpResolver::ipinfo(cache()).urls(),
+            (Some("https://ipinfo.io/json"), Some("https://v6.ipinfo.io/json"))
+        );
+        assert_eq!(lookup_ipv4(HttpResolver::ipinfo(cache()), "/json", &body(IPV4)).unwrap().to_string(), IPV4);
+        assert_eq!(lookup_ipv6(HttpResolver::ipinfo(cache()), "/json", &body(IPV6)).unwrap().to_string(), IPV6);
+    }
+
+    #[test]
+    fn test_aws_checkip_only_answers_on_ipv4() {
+        assert_eq!(HttpResolver::aws_checkip(cache()).urls(), (Some("https://checkip.amazonaws.com"), None));
+        assert_eq!(
+            lookup_ipv4(HttpResolver::aws_checkip(cache()), "/", &format!("{}\n", IPV4)).unwrap().to_string(),
+            IPV4
+        );
+        assert!(matches!(HttpResolver::aws_checkip(cache()).get_ipv6(), Err(ResolverError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn test_cloudflare_trace() {
+        let trace =
+            |ip: &str| format!("fl=12f34\nh=www.cloudflare.com\nip={}\nts=1735311227.123\nvisit_scheme=https\n", ip);
+
+        assert_eq!(
+            HttpResolver::cloudflare_trace(cache()).urls(),
+            (Some("https://www.cloudflare.com/cdn-cgi/trace"), Some("https://www.cloudflare.com/cdn-cgi/trace"))
+        );
+        assert_eq!(
+            lookup_ipv4(HttpResolver::cloudflare_trace(cache()), "/cdn-cgi/trace", &trace(IPV4)).unwrap().to_string(),
+            IPV4
+        );
+        assert_eq!(
+            lookup_ipv6(HttpResolver::cloudflare_trace(cache()), "/cdn-cgi/trace", &trace(IPV6)).unwrap().to_string(),
+            IPV6
+        );
+        // Without the `ip` key there is nothing to parse.
+        assert!(matches!(
+            lookup_ipv4(HttpResolver::cloudflare_trace(cache()), "/cdn-cgi/trace", "fl=12f34\nloc=DE\n"),
+            Err(ResolverError::InvalidAddress { .. })
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::ip::cache::IpCache;
+use crate::ip::resolver::IpResolver;
+use crate::ip::resolver::error::ResolverError;
+use crate::parallel::map_parallel;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/**
+Asks all resolvers at once and only accepts an address that at least `quorum` of them return.
+The resolvers must not share a cache with anything else, otherwise they wouldn't return independent answers.
+Agreed addresses are cached in a private cache, so an address from another resolver can never skip the vote.
+It is expired on [`IpResolver::invalidate`] before every check, like the cache of the group. The latter is never read. It only gets the agreed addresses, so the stabilization window can observe them.
+*/
+pub(crate) struct ConsensusResolver {
+    cache: IpCache,
+    group_cache: Arc<IpCache>,
+    sources: Vec<(String, Box<dyn IpResolver>)>,
+    quorum: usize,
+}
+
+impl ConsensusResolver {
+    pub(crate) fn new(group_cache: Arc<IpCache>, sources: Vec<(String, Box<dyn IpResolver>)>, quorum: usize) -> Self {
+        Self {
+            cache: IpCache::new(group_cache.ttl()),
+            group_cache,
+            sources,
+            quorum,
+        }
+    }
+
+    fn resolve<T: Copy + Eq + Hash + Display + Send>(
+        &self,
+        family: &'static str,
+        lookup: impl Fn(&dyn IpResolver) -> Result<T, ResolverError> + Sync,
+    ) -> Result<T, ResolverError> {
+        let sources: Vec<&(String, Box<dyn IpResolver>)> = self.sources.iter().collect();
+        let results = map_parallel(sources, self.sources.len(), |(name, resolver)| (name, lookup(resolver.as_ref())));
+
+        let mut votes: HashMap<T, usize> = HashMap::new();
+        let mut answers = Vec::new();
+        let mut last_error = None;
+        for (name, result) in results {
+            match result {
+                Ok(ip) => {
+                    *votes.entry(ip).or_default() += 1;
+                    answers.push(format!("{} returned {}", name, ip));
+                }
+                Err(ResolverError::Unsupported {
+                    ..
+                }) => {}
+                Err(err) => {
+                    warn!("The {} resolver failed to look up the {} address: {}", name, family, err);
+                    answers.push(format!("{} failed", name));
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        let agreed: Vec<T> = votes.iter().filter(|(_, count)| **count >= self.quorum).map(|(ip, _)| *ip).collect();
+        match (agreed.as_slice(), last_error) {
+            ([ip], _) => {
+                debug!("{} of the resolvers agree on the public {} address {}", votes[ip], family, ip);
+                Ok(*ip)
+            }
+            // Nobody answered, so keep the original error. It may be worth a retry.
+            ([], Some(err)) if votes.is_empty() => Err(err),
+            _ => Err(ResolverError::no_consensus(family, self.quorum, &answers)),
+        }
+    }
+}
+
+impl IpResolver for ConsensusResolver {
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+        if let Some(ipv4addr) = self.cache.get_ipv4addr() {
+            return Ok(ipv4addr);
+        }
+
+        let ip = self.resolve("IPv4", |resolver| resolver.get_ipv4())?;
+        self.cache.set_ipv4addr(ip);
+        self.group_cache.set_ipv4addr(ip);
+
+        Ok(ip)
+    }
+
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+        if let Some(ipv6addr) = self.cache.get_ipv6addr() {
+            return Ok(ipv6addr);
+        }
+
+        let ip = self.resolve("IPv6", |resolver| resolver.get_ipv6())?;
+        self.cache.set_ipv6addr(ip);
+        self.group_cache.set_ipv6addr(ip);
+
+        Ok(ip)
+    }
+
+    fn invalidate(&self) {
+        self.cache.invalidate();
+        self.sources.iter().for_each(|(_, resolver)| resolver.invalidate());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::cache::IpCache;
+    use crate::ip::resolver::IpResolver;
+    use crate::ip::resolver::consensus::ConsensusResolver;
+    use crate::ip::resolver::error::ResolverError;
+    use reqwest::StatusCode;
+    use reqwest::header::HeaderMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::{Arc, Mutex};
+
+    struct StubResolver(Option<Ipv4Addr>);
+
+    impl IpResolver for StubResolver {
+        fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+            self.0.ok_or_else(|| ResolverError::status("stub", StatusCode::BAD_GATEWAY, &HeaderMap::new()))
+        }
+
+        fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+            Err(ResolverError::unsupported("stub", "IPv6"))
+        }
+    }
+
+    const IP: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 1);
+    const OTHER_IP: Ipv4Addr = Ipv4Addr::new(198, 51, 100, 7);
+
+    fn consensus(cache: Arc<IpCache>, answers: Vec<Option<Ipv4Addr>>, quorum: usize) -> ConsensusResolver {
+        let sources = answers
+            .into_iter()
+            .enumerate()
+            .map(|(index, answer)| (format!("stub {}", index), Box::new(StubResolver(answer)) as Box<dyn IpResolver>))
+            .collect();
+
+        ConsensusResolver::new(cache, sources, quorum)
+    }
+
+    #[test]
+    fn test_agreeing_majority_is_accepted_and_cached() {
+        let cache = Arc::new(IpCache::new(60));
+        let resolver = consensus(cache.clone(), vec![Some(IP), Some(OTHER_IP), Some(IP)], 2);
+
+        assert_eq!(resolver.get_ipv4().unwrap(), IP);
+        assert_eq!(cache.get_ipv4addr(), Some(IP));
+    }
+
+    #[test]
+    fn test_disagreement_is_an_error_and_not_cached() {
+        let cache = Arc::new(IpCache::new(60));
+        let resolver = consensus(cache.clone(), vec![Some(IP), Some(OTHER_IP), None], 2);
+
+        let err = resolver.get_ipv4().unwrap_err();
+        assert!(matches!(err, ResolverError::NoConsensus { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Fewer than 2 resolvers agree on the public IPv4 address: \
+             stub 0 returned 203.0.113.1, stub 1 returned 198.51.100.7, stub 2 failed"
+        );
+        assert_eq!(cache.get_ipv4addr(), None);
+    }
+
+    #[test]
+    fn test_two_addresses_reaching_the_quorum_is_no_consensus() {
+        let resolver =
+            consensus(Arc::new(IpCache::new(60)), vec![Some(IP), Some(OTHER_IP), Some(IP), Some(OTHER_IP)], 2);
+
+        assert!(matches!(resolver.get_ipv4(), Err(ResolverError::NoConsensus { .. })));
+    }
+
+    #[test]
+    fn test_no_answers_keep_the_original_error() {
+        let resolver = consensus(Arc::new(IpCache::new(60)), vec![None, None], 1);
+
+        assert!(matches!(resolver.get_ipv4(), Err(ResolverError::Status { .. })));
+        assert!(matches!(resolver.get_ipv6(), Err(ResolverError::NoConsensus { .. })));
+    }
+
+    #[test]
+    fn test_the_group_cache_cant_skip_the_vote() {
+        let group_cache = Arc::new(IpCache::new(60));
+        // E.g. written by another resolver of a fallback chain.
+        group_cache.set_ipv4addr(OTHER_IP);
+        let resolver = consensus(group_cache.clone(), vec![Some(IP), Some(IP), None], 2);
+
+        assert_eq!(resolver.get_ipv4().unwrap(), IP);
+        assert_eq!(group_cache.get_ipv4addr(), Some(IP));
+    }
+
+    /**
+    Answers with whatever the test put in, so the answer can change between two checks.
+    */
+    struct ChangingResolver(Arc<Mutex<Ipv4Addr>>);
+
+    impl IpResolver for ChangingResolver {
+        fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+            Ok(*self.0.lock().unwrap())
+        }
+
+        fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+            Err(ResolverError::unsupported("changing", "IPv6"))
+        }
+    }
+
+    #[test]
+    fn test_every_check_votes_again() {
+        let answer = Arc::new(Mutex::new(IP));
+        let sources: Vec<(String, Box<dyn IpResolver>)> = (0..3)
+            .map(|index| {
+                (format!("changing {}", index), Box::new(ChangingResolver(answer.clone())) as Box<dyn IpResolver>)
+            })
+            .collect();
+        let group_cache = Arc::new(IpCache::new(60));
+        let resolver = ConsensusResolver::new(group_cache.clone(), sources, 2);
+
+        assert_eq!(resolver.get_ipv4().unwrap(), IP);
+
+        // The next check, as the runner does it.
+        *answer.lock().unwrap() = OTHER_IP;
+        group_cache.invalidate();
+        resolver.invalidate();
+
+        assert_eq!(resolver.get_ipv4().unwrap(), OTHER_IP);
+        assert_eq!(group_cache.get_ipv4addr(), Some(OTHER_IP));
+    }
+}
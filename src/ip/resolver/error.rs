@@ -0,0 +1,196 @@
+use crate::retry::TransientError;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum ResolverError {
+    #[error("Could not reach {url}: {source}")]
+    Transport {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("{url} responded with HTTP {status}")]
+    Status {
+        url: String,
+        status: StatusCode,
+        retry_after: Option<Duration>,
+    },
+    #[error("{url} did not return a valid IP address: {content:?}")]
+    InvalidAddress {
+        url: String,
+        content: String,
+    },
+    #[error("The {resolver} resolver can't look up {family} addresses")]
+    Unsupported {
+        resolver: String,
+        family: &'static str,
+    },
+    #[error("Could not read the addresses of the interface {interface}: {reason}")]
+    InterfaceUnavailable {
+        interface: String,
+        reason: String,
+    },
+    #[error("The kernel did not list the addresses of the interface {interface} in time")]
+    InterfaceTimeout {
+        interface: String,
+    },
+    #[error("The interface {interface} has no public {family} address")]
+    NoGlobalAddress {
+        interface: String,
+        family: &'static str,
+    },
+    #[error("Fewer than {quorum} resolvers agree on the public {family} address: {answers}")]
+    NoConsensus {
+        family: &'static str,
+        quorum: usize,
+        answers: String,
+    },
+}
+
+impl ResolverError {
+    pub(crate) fn transport(url: &str, source: reqwest::Error) -> Self {
+        Self::Transport {
+            url: url.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn status(url: &str, status: StatusCode, headers: &HeaderMap) -> Self {
+        Self::Status {
+            url: url.to_string(),
+            status,
+            retry_after: headers
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs),
+        }
+    }
+
+    pub(crate) fn invalid_address(url: &str, content: &str) -> Self {
+        Self::InvalidAddress {
+            url: url.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    pub(crate) fn unsupported(resolver: &str, family: &'static str) -> Self {
+        Self::Unsupported {
+            resolver: resolver.to_string(),
+            family,
+        }
+    }
+
+    pub(crate) fn interface_unavailable(interface: &str, reason: &str) -> Self {
+        Self::InterfaceUnavailable {
+            interface: interface.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn interface_timeout(interface: &str) -> Self {
+        Self::InterfaceTimeout {
+            interface: interface.to_string(),
+        }
+    }
+
+    pub(crate) fn no_global_address(interface: &str, family: &'static str) -> Self {
+        Self::NoGlobalAddress {
+            interface: interface.to_string(),
+            family,
+        }
+    }
+
+    pub(crate) fn no_consensus(family: &'static str, quorum: usize, answers: &[String]) -> Self {
+        Self::NoConsensus {
+            family,
+            quorum,
+            answers: answers.join(", "),
+        }
+    }
+}
+
+impl TransientError for ResolverError {
+    fn is_transient(&self) -> bool {
+        match self {
+            ResolverError::Transport {
+                source,
+                ..
+            } => source.is_timeout() || source.is_connect() || source.is_request() || source.is_body(),
+            ResolverError::Status {
+                status,
+                ..
+            } => {
+                status.is_server_error()
+                    || *status == StatusCode::REQUEST_TIMEOUT
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            // The address may just not be assigned yet, e.g. while a PPPoE link reconnects.
+            ResolverError::NoGlobalAddress {
+                ..
+            }
+            | ResolverError::InterfaceTimeout {
+                ..
+            } => true,
+            ResolverError::InvalidAddress {
+                ..
+            }
+            | ResolverError::InterfaceUnavailable {
+                ..
+            }
+            | ResolverError::Unsupported {
+                ..
+            }
+            | ResolverError::NoConsensus {
+                ..
+            } => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ResolverError::Status {
+                retry_after,
+                ..
+            } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::resolver::error::ResolverError;
+    use crate::retry::TransientError;
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderMap, RETRY_AFTER};
+    use std::time::Duration;
+
+    #[test]
+    fn test_server_errors_are_transient() {
+        let err = ResolverError::status("https://example.internal", StatusCode::BAD_GATEWAY, &HeaderMap::new());
+
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn test_invalid_address_is_permanent() {
+        let err = ResolverError::invalid_address("https://example.internal", "<html></html>");
+
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn test_retry_after_is_parsed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "60".parse().unwrap());
+        let err = ResolverError::status("https://example.internal", StatusCode::TOO_MANY_REQUESTS, &headers);
+
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::ip::resolver::IpResolver;
+use crate::ip::resolver::error::ResolverError;
+use humantime_serde::re::humantime::format_duration;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/**
+Tries the resolvers in order and returns the first valid address, so an outage of one service doesn't block the sync.
+A resolver that fails `max_failures` times in a row sits out for the `cooldown`. This is tracked per IP family.
+*/
+pub(crate) struct FallbackResolver {
+    sources: Vec<Source>,
+    max_failures: u32,
+    cooldown: Duration,
+}
+
+struct Source {
+    name: String,
+    resolver: Box<dyn IpResolver>,
+    ipv4_health: Mutex<Health>,
+    ipv6_health: Mutex<Health>,
+}
+
+#[derive(Default)]
+struct Health {
+    failures: u32,
+    sits_out_until: Option<Instant>,
+}
+
+impl Health {
+    fn is_sitting_out(&self, now: Instant) -> bool {
+        self.sits_out_until.is_some_and(|until| until > now)
+    }
+}
+
+impl FallbackResolver {
+    pub(crate) fn new(sources: Vec<(String, Box<dyn IpResolver>)>, max_failures: u32, cooldown: Duration) -> Self {
+        Self {
+            sources: sources
+                .into_iter()
+                .map(|(name, resolver)| Source {
+                    name,
+                    resolver,
+                    ipv4_health: Mutex::default(),
+                    ipv6_health: Mutex::default(),
+                })
+                .collect(),
+            max_failures,
+            cooldown,
+        }
+    }
+
+    fn resolve<T>(
+        &self,
+        family: &'static str,
+        health: impl Fn(&Source) -> &Mutex<Health>,
+        lookup: impl Fn(&dyn IpResolver) -> Result<T, ResolverError>,
+    ) -> Result<T, ResolverError> {
+        let now = Instant::now();
+        let mut candidates: Vec<&Source> =
+            self.sources.iter().filter(|source| !Self::lock(health(source)).is_sitting_out(now)).collect();
+        if candidates.is_empty() {
+            debug!("All {} resolvers are sitting out, trying all of them anyway", family);
+            candidates = self.sources.iter().collect();
+        }
+
+        let mut last_error = None;
+        let mut failed = false;
+        for source in candidates {
+            match lookup(source.resolver.as_ref()) {
+                Ok(ip) => {
+                    *Self::lock(health(source)) = Health::default();
+                    match failed {
+                        true => info!("Resolved the public {} address with the {} resolver", family, source.name),
+                        false => debug!("Resolved the public {} address with the {} resolver", family, source.name),
+                    }
+                    return Ok(ip);
+                }
+                // Not a failure of the service, it just doesn't answer for this family.
+                Err(err) if matches!(err, ResolverError::Unsupported { .. }) => {
+                    last_error.get_or_insert(err);
+                }
+                Err(err) => {
+                    warn!("The {} resolver failed to look up the {} address: {}", source.name, family, err);
+                    self.record_failure(source, &mut Self::lock(health(source)), now);
+                    failed = true;
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ResolverError::unsupported("fallback", family)))
+    }
+
+    fn record_failure(&self, source: &Source, health: &mut Health, now: Instant) {
+        health.failures += 1;
+        if health.failures >= self.max_failures {
+            warn!(
+                "The {} resolver failed {} times in a row and sits out for {}",
+                source.name,
+                health.failures,
+                format_duration(self.cooldown)
+            );
+            *health = Health {
+                failures: 0,
+                sits_out_until: now.checked_add(self.cooldown),
+            };
+        }
+    }
+
+    #[allow(clippy::expect_used)]
+    fn lock(health: &Mutex<Health>) -> std::sync::MutexGuard<'_, Health> {
+        health.lock().expect("Resolver health lock poisoned")
+    }
+}
+
+impl IpResolver for FallbackResolver {
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+        self.resolve("IPv4", |source| &source.ipv4_health, |resolver| resolver.get_ipv4())
+    }
+
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+        self.resolve("IPv6", |source| &source.ipv6_health, |resolver| resolver.get_ipv6())
+    }
+
+    fn invalidate(&self) {
+        self.sources.iter().for_each(|source| source.resolver.invalidate());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::resolver::IpResolver;
+    use crate::ip::resolver::error::ResolverError;
+    use crate::ip::resolver::fallback::FallbackResolver;
+    use reqwest::StatusCode;
+    use reqwest::header::HeaderMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    struct StubResolver {
+        ipv4: Option<Ipv4Addr>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl StubResolver {
+        fn boxed(ipv4: Option<Ipv4Addr>) -> (Box<dyn IpResolver>, Arc<AtomicU32>) {
+            let calls = Arc::new(AtomicU32::new(0));
+            (
+                Box::new(Self {
+                    ipv4,
+                    calls: calls.clone(),
+                }),
+                calls,
+            )
+        }
+    }
+
+    impl IpResolver for StubResolver {
+        fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.ipv4.ok_or_else(|| ResolverError::status("stub", StatusCode::BAD_GATEWAY, &HeaderMap::new()))
+        }
+
+        fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+            Err(ResolverError::unsupported("stub", "IPv6"))
+        }
+    }
+
+    const IP: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 1);
+
+    #[test]
+    fn test_first_answer_wins() {
+        let (first, first_calls) = StubResolver::boxed(Some(IP));
+        let (second, second_calls) = StubResolver::boxed(Some(Ipv4Addr::LOCALHOST));
+        let resolver =
+            FallbackResolver::new(vec![("first".into(), first), ("second".into(), second)], 3, Duration::from_secs(60));
+
+        assert_eq!(resolver.get_ipv4().unwrap(), IP);
+        assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(second_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_falls_back_on_failure() {
+        let (failing, _) = StubResolver::boxed(None);
+        let (working, _) = StubResolver::boxed(Some(IP));
+        let resolver = FallbackResolver::new(
+            vec![("failing".into(), failing), ("working".into(), working)],
+            3,
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(resolver.get_ipv4().unwrap(), IP);
+    }
+
+    #[test]
+    fn test_failing_resolver_sits_out() {
+        let (failing, failing_calls) = StubResolver::boxed(None);
+        let (working, _) = StubResolver::boxed(Some(IP));
+        let resolver = FallbackResolver::new(
+            vec![("failing".into(), failing), ("working".into(), working)],
+            2,
+            Duration::from_secs(60),
+        );
+
+        for _ in 0..4 {
+            assert_eq!(resolver.get_ipv4().unwrap(), IP);
+        }
+
+        assert_eq!(failing_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_all_failing_returns_the_last_error() {
+        let (first, _) = StubResolver::boxed(None);
+        let (second, second_calls) = StubResolver::boxed(None);
+        let resolver =
+            FallbackResolver::new(vec![("first".into(), first), ("second".into(), second)], 1, Duration::from_secs(60));
+
+        assert!(matches!(resolver.get_ipv4(), Err(ResolverError::Status { .. })));
+        // Everything sits out now, so all resolvers are tried again instead of giving up.
+        assert!(resolver.get_ipv4().is_err());
+        assert_eq!(second_calls.load(Ordering::SeqCst), 2);
+        assert!(matches!(resolver.get_ipv6(), Err(ResolverError::Unsupported { .. })));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::configuration::user::resolver::{Extraction, HttpResolverConfig};
+use crate::ip::cache::IpCache;
+use crate::ip::resolver::IpResolver;
+use crate::ip::resolver::error::ResolverError;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/**
+How the address is extracted from the response body of an IP lookup service.
+*/
+#[derive(Debug, Clone)]
+pub(crate) enum ResponseFormat {
+    /**
+    The body is nothing but the address.
+    */
+    PlainText,
+    /**
+    The address is a string in a JSON document, e.g. `/ip` for `{"ip": "203.0.113.1"}`.
+    */
+    JsonPointer(String),
+    /**
+    The body has one `key=value` pair per line, e.g. Cloudflare's `/cdn-cgi/trace`.
+    */
+    KeyValue(String),
+    /**
+    The address is the first capture group of the regex.
+    */
+    Regex(Regex),
+}
+
+impl From<&Extraction> for ResponseFormat {
+    fn from(extraction: &Extraction) -> Self {
+        match extraction {
+            Extraction::Body => ResponseFormat::PlainText,
+            Extraction::Regex(pattern) => ResponseFormat::Regex(pattern.0.clone()),
+            Extraction::JsonPointer(pointer) => ResponseFormat::JsonPointer(pointer.clone()),
+        }
+    }
+}
+
+impl ResponseFormat {
+    pub(crate) fn extract(&self, body: &str) -> Option<String> {
+        match self {
+            ResponseFormat::PlainText => Some(body.trim().to_string()),
+            ResponseFormat::JsonPointer(pointer) => serde_json::from_str::<serde_json::Value>(body)
+                .ok()?
+                .pointer(pointer)?
+                .as_str()
+                .map(|value| value.trim().to_string()),
+            ResponseFormat::KeyValue(key) => body
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(name, _)| name.trim() == key)
+                .map(|(_, value)| value.trim().to_string()),
+            ResponseFormat::Regex(regex) => {
+                regex.captures(body)?.get(1).map(|address| address.as_str().trim().to_string())
+            }
+        }
+    }
+}
+
+/**
+A client whose connections only use IPv4, so services that answer on both families return the IPv4 address.
+*/
+pub(crate) fn ipv4_client() -> Client {
+    family_client(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/**
+A client whose connections only use IPv6, so services that answer on both families return the IPv6 address.
+*/
+pub(crate) fn ipv6_client() -> Client {
+    family_client(IpAddr::V6(Ipv6Addr::UNSPECIFIED))
+}
+
+fn family_client(local_address: IpAddr) -> Client {
+    // Building only fails when the TLS backend can't be initialized, which makes `Client::new` fail just the same.
+    Client::builder().local_address(local_address).build().unwrap_or_else(|_| Client::new())
+}
+
+/**
+A URL that returns the public address of one IP family.
+*/
+pub(crate) struct HttpEndpoint {
+    url: String,
+    format: ResponseFormat,
+    client: Client,
+    headers: HeaderMap,
+}
+
+impl HttpEndpoint {
+    pub(crate) fn new(url: &str, format: ResponseFormat, client: Client) -> Self {
+        Self {
+            url: url.to_string(),
+            format,
+            client,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /**
+    Sends the headers with every request. Invalid names and values are skipped, validation already rejects them.
+    */
+    pub(crate) fn with_headers(mut self, headers: &BTreeMap<String, String>) -> Self {
+        self.headers = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((HeaderName::from_bytes(name.as_bytes()).ok()?, HeaderValue::from_str(value).ok()?))
+            })
+            .collect();
+        self
+    }
+
+    fn fetch<T: FromStr>(&self) -> Result<T, ResolverError> {
+        let response = self
+            .client
+            .get(&self.url)
+            .headers(self.headers.clone())
+            .send()
+            .map_err(|err| ResolverError::transport(self.url.as_str(), err))?;
+        if !response.status().is_success() {
+            return Err(ResolverError::status(self.url.as_str(), response.status(), response.headers()));
+        }
+
+        let content = response.text().map_err(|err| ResolverError::transport(self.url.as_str(), err))?;
+
+        self.format
+            .extract(&content)
+            .and_then(|address| address.parse::<T>().ok())
+            .ok_or_else(|| ResolverError::invalid_address(self.url.as_str(), content.trim()))
+    }
+}
+
+/**
+Looks up the public IP with an HTTP service. Services that only support one IP family leave the other endpoint empty.
+*/
+pub(crate) struct HttpResolver {
+    name: String,
+    cache: Arc<IpCache>,
+    ipv4: Option<HttpEndpoint>,
+    ipv6: Option<HttpEndpoint>,
+}
+
+impl HttpResolver {
+    pub(crate) fn new(name: &str, cache: Arc<IpCache>, ipv4: Option<HttpEndpoint>, ipv6: Option<HttpEndpoint>) -> Self {
+        Self {
+            name: name.to_string(),
+            cache,
+            ipv4,
+            ipv6,
+        }
+    }
+
+    /**
+    A lookup service from the config. Each URL only has to answer for its own IP family.
+    */
+    pub(crate) fn custom(config: &HttpResolverConfig, cache: Arc<IpCache>) -> Self {
+        let format = ResponseFormat::from(&config.extract);
+        let endpoint =
+            |url: &String, client: Client| HttpEndpoint::new(url, format.clone(), client).with_headers(&config.headers);
+
+        Self::new(
+            "HTTP",
+            cache,
+            config.ipv4_url.as_ref().map(|url| endpoint(url, ipv4_client())),
+            config.ipv6_url.as_ref().map(|url| endpoint(url, ipv6_client())),
+        )
+    }
+}
+
+#[cfg(test)]
+impl HttpResolver {
+    pub(crate) fn urls(&self) -> (Option<&str>, Option<&str>) {
+        (
+            self.ipv4.as_ref().map(|endpoint| endpoint.url.as_str()),
+            self.ipv6.as_ref().map(|endpoint| endpoint.url.as_str()),
+        )
+    }
+
+    /**
+    Sends the requests to a mock server instead, keeping the path of every URL.
+    The mock server only listens on IPv4, so the clients that are pinned to an IP family are replaced as well.
+    */
+    pub(crate) fn on_server(mut self, server_url: &str) -> Self {
+        for endpoint in self.ipv4.iter_mut().chain(self.ipv6.iter_mut()) {
+            let path = reqwest::Url::parse(&endpoint.url).map(|url| url.path().to_string()).unwrap_or_default();
+            endpoint.url = format!("{}{}", server_url, path);
+            endpoint.client = Client::new();
+        }
+        self
+    }
+}
+
+impl IpResolver for HttpResolver {
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+        if let Some(ipv4addr) = self.cache.get_ipv4addr() {
+            return Ok(ipv4addr);
+        }
+
+        let endpoint = self.ipv4.as_ref().ok_or_else(|| ResolverError::unsupported(&self.name, "IPv4"))?;
+        let ip = endpoint.fetch::<Ipv4Addr>()?;
+        self.cache.set_ipv4addr(ip);
+
+        Ok(ip)
+    }
+
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+        if let Some(ipv6addr) = self.cache.get_ipv6addr() {
+            return Ok(ipv6addr);
+        }
+
+        let endpoint = self.ipv6.as_ref().ok_or_else(|| ResolverError::unsupported(&self.name, "IPv6"))?;
+        let ip = endpoint.fetch::<Ipv6Addr>()?;
+        self.cache.set_ipv6addr(ip);
+
+        Ok(ip)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::cache::IpCache;
+    use crate::ip::resolver::IpResolver;
+    use crate::ip::resolver::error::ResolverError;
+    use crate::ip::resolver::http::{HttpEndpoint, HttpResolver, ResponseFormat};
+    use mockito::Server;
+    use regex::Regex;
+    use reqwest::blocking::Client;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_extract_plain_text() {
+        assert_eq!(ResponseFormat::PlainText.extract("203.0.113.1\n"), Some("203.0.113.1".to_string()));
+    }
+
+    #[test]
+    fn test_extract_json_pointer() {
+        let format = ResponseFormat::JsonPointer("/ip".to_string());
+        let nested = ResponseFormat::JsonPointer("/data/0/address".to_string());
+
+        assert_eq!(format.extract(r#"{"ip": "203.0.113.1", "country": "DE"}"#), Some("203.0.113.1".to_string()));
+        assert_eq!(format.extract(r#"{"address": "203.0.113.1"}"#), None);
+        assert_eq!(format.extract("203.0.113.1"), None);
+        assert_eq!(nested.extract(r#"{"data": [{"address": "2001:db8::1"}]}"#), Some("2001:db8::1".to_string()));
+    }
+
+    #[test]
+    fn test_extract_regex_capture_group() {
+        let format = ResponseFormat::Regex(Regex::new(r"Current IP Address: ([\d.]+)").unwrap());
+
+        assert_eq!(
+            format.extract("<html><body>Current IP Address: 203.0.113.1</body></html>"),
+            Some("203.0.113.1".to_string())
+        );
+        assert_eq!(format.extract("<html><body>Blocked</body></html>"), None);
+    }
+
+    #[test]
+    fn test_extract_key_value() {
+        let format = ResponseFormat::KeyValue("ip".to_string());
+        let trace = "fl=12f34\nh=www.cloudflare.com\nip=2001:db8::1\nts=1735311227.123\nvisit_scheme=https\n";
+
+        assert_eq!(format.extract(trace), Some("2001:db8::1".to_string()));
+        assert_eq!(ResponseFormat::KeyValue("loc".to_string()).extract(trace), None);
+    }
+
+    #[test]
+    fn test_resolve_and_cache_ipv4() {
+        let mut server = Server::new();
+        let mock =
+            server.mock("GET", "/json").with_status(200).with_body(r#"{"ip": "203.0.113.1"}"#).expect(1).create();
+
+        let resolver = HttpResolver::new(
+            "test",
+            Arc::new(IpCache::new(60)),
+            Some(HttpEndpoint::new(
+                &format!("{}/json", server.url()),
+                ResponseFormat::JsonPointer("/ip".to_string()),
+                Client::new(),
+            )),
+            None,
+        );
+
+        assert_eq!(resolver.get_ipv4().unwrap().to_string(), "203.0.113.1");
+        assert_eq!(resolver.get_ipv4().unwrap().to_string(), "203.0.113.1");
+
+        mock.assert();
+    }
+
+    #[test]
+    fn test_invalid_response_is_an_error() {
+        let mut server = Server::new();
+        server.mock("GET", "/").with_status(200).with_body("<html>Blocked</html>").create();
+
+        let resolver = HttpResolver::new(
+            "test",
+            Arc::new(IpCache::new(60)),
+            Some(HttpEndpoint::new(&server.url(), ResponseFormat::PlainText, Client::new())),
+            None,
+        );
+
+        assert!(matches!(resolver.get_ipv4(), Err(ResolverError::InvalidAddress { .. })));
+    }
+
+    #[test]
+    fn test_missing_family_is_unsupported() {
+        let resolver = HttpResolver::new("test", Arc::new(IpCache::new(60)), None, None);
+
+        assert!(matches!(resolver.get_ipv6(), Err(ResolverError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn test_headers_are_sent() {
+        let mut server = Server::new();
+        let mock = server
+            .mock("GET", "/")
+            .match_header("authorization", "Bearer secret")
+            .with_status(200)
+            .with_body("203.0.113.1")
+            .expect(1)
+            .create();
+        let headers = BTreeMap::from([("Authorization".to_string(), "Bearer secret".to_string())]);
+
+        let resolver = HttpResolver::new(
+            "test",
+            Arc::new(IpCache::new(60)),
+            Some(HttpEndpoint::new(&server.url(), ResponseFormat::PlainText, Client::new()).with_headers(&headers)),
+            None,
+        );
+
+        assert_eq!(resolver.get_ipv4().unwrap().to_string(), "203.0.113.1");
+
+        mock.assert();
+    }
+}
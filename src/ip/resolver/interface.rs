@@ -0,0 +1,144 @@
+use crate::ip::cache::IpCache;
+use crate::ip::resolver::IpResolver;
+use crate::ip::resolver::error::ResolverError;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+/**
+Reads the public IP from a local network interface instead of asking a service, e.g. on PPPoE routers or a VPS.
+The addresses are read from the kernel via netlink, so this only works on Linux.
+Only addresses with a global scope that are publicly routable count.
+*/
+pub(crate) struct InterfaceResolver {
+    name: String,
+    cache: Arc<IpCache>,
+}
+
+impl InterfaceResolver {
+    pub(crate) fn new(name: &str, cache: Arc<IpCache>) -> Self {
+        Self {
+            name: name.to_string(),
+            cache,
+        }
+    }
+
+    fn public_addresses(&self) -> Result<Vec<IpAddr>, ResolverError> {
+        let addresses = global_addresses(&self.name).map_err(|err| lookup_error(&self.name, err))?;
+
+        Ok(addresses.into_iter().filter(is_public).collect())
+    }
+}
+
+fn lookup_error(interface: &str, err: io::Error) -> ResolverError {
+    match err.kind() {
+        // The receive timeout of the socket ran out, the next try may well succeed.
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => ResolverError::interface_timeout(interface),
+        _ => ResolverError::interface_unavailable(interface, &err.to_string()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn global_addresses(interface: &str) -> io::Result<Vec<IpAddr>> {
+    crate::ip::resolver::netlink::global_addresses(interface)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn global_addresses(_interface: &str) -> io::Result<Vec<IpAddr>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Interfaces can only be read on Linux"))
+}
+
+/**
+The kernel also gives private and unique local addresses a global scope, but they can't be reached from the internet.
+*/
+pub(crate) fn is_public(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(ip) => {
+            // 100.64.0.0/10 is used for carrier-grade NAT.
+            let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0b1100_0000) == 64;
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || shared)
+        }
+        IpAddr::V6(ip) => {
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local())
+        }
+    }
+}
+
+impl IpResolver for InterfaceResolver {
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+        if let Some(ipv4addr) = self.cache.get_ipv4addr() {
+            return Ok(ipv4addr);
+        }
+
+        let ip = self
+            .public_addresses()?
+            .into_iter()
+            .find_map(|address| match address {
+                IpAddr::V4(ip) => Some(ip),
+                IpAddr::V6(_) => None,
+            })
+            .ok_or_else(|| ResolverError::no_global_address(&self.name, "IPv4"))?;
+        self.cache.set_ipv4addr(ip);
+
+        Ok(ip)
+    }
+
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+        if let Some(ipv6addr) = self.cache.get_ipv6addr() {
+            return Ok(ipv6addr);
+        }
+
+        let ip = self
+            .public_addresses()?
+            .into_iter()
+            .find_map(|address| match address {
+                IpAddr::V6(ip) => Some(ip),
+                IpAddr::V4(_) => None,
+            })
+            .ok_or_else(|| ResolverError::no_global_address(&self.name, "IPv6"))?;
+        self.cache.set_ipv6addr(ip);
+
+        Ok(ip)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::cache::IpCache;
+    use crate::ip::resolver::IpResolver;
+    use crate::ip::resolver::error::ResolverError;
+    use crate::ip::resolver::interface::{InterfaceResolver, is_public, lookup_error};
+    use crate::retry::TransientError;
+    use std::io;
+    use std::net::IpAddr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_only_public_addresses_count() {
+        let public = ["198.51.100.7", "2001:db8::1"];
+        let private =
+            ["192.168.1.10", "10.0.0.1", "100.72.1.5", "169.254.1.1", "127.0.0.1", "fd00::2", "fe80::1", "::1"];
+
+        assert!(public.iter().all(|ip| is_public(&ip.parse::<IpAddr>().unwrap())));
+        assert!(private.iter().all(|ip| !is_public(&ip.parse::<IpAddr>().unwrap())));
+    }
+
+    #[test]
+    fn test_unknown_interface_is_an_error() {
+        let resolver = InterfaceResolver::new("dyncloud-none0", Arc::new(IpCache::new(60)));
+
+        assert!(matches!(resolver.get_ipv4(), Err(ResolverError::InterfaceUnavailable { .. })));
+    }
+
+    #[test]
+    fn test_timeout_is_transient() {
+        let timeout = lookup_error("ppp0", io::Error::from(io::ErrorKind::WouldBlock));
+        let missing = lookup_error("ppp0", io::Error::from(io::ErrorKind::NotFound));
+
+        assert!(matches!(timeout, ResolverError::InterfaceTimeout { .. }));
+        assert!(timeout.is_transient());
+        assert!(matches!(missing, ResolverError::InterfaceUnavailable { .. }));
+        assert!(!missing.is_transient());
+    }
+}
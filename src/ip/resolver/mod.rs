@@ -1,8 +1,23 @@
-pub(crate) mod ipify;
+pub(crate) mod builtin;
+pub(crate) mod consensus;
+pub(crate) mod error;
+pub(crate) mod fallback;
+pub(crate) mod http;
+pub(crate) mod interface;
+#[cfg(target_os = "linux")]
+pub(crate) mod netlink;
+pub(crate) mod retry;
 
+use crate::ip::resolver::error::ResolverError;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 pub(crate) trait IpResolver: Send + Sync {
-    fn get_ipv4(&self) -> Result<Ipv4Addr, anyhow::Error>;
-    fn get_ipv6(&self) -> Result<Ipv6Addr, anyhow::Error>;
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError>;
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError>;
+
+    /**
+    Called before every scheduled check, next to the invalidation of the group's cache.
+    Resolvers that keep a cache of their own must expire it here, so the check sees a fresh address.
+    */
+    fn invalidate(&self) {}
 }
@@ -0,0 +1,264 @@
+use rustix::net::netdevice::name_to_index;
+use rustix::net::netlink::SocketAddrNetlink;
+use rustix::net::sockopt::{Timeout, set_socket_timeout};
+use rustix::net::{AddressFamily, RecvFlags, SendFlags, SocketType, recv, sendto, socket};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+// Constants from linux/netlink.h, linux/rtnetlink.h and linux/if_addr.h
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_DUMP: u16 = 0x300;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_FLAGS: u16 = 8;
+const IFA_F_DADFAILED: u32 = 0x08;
+const IFA_F_DEPRECATED: u32 = 0x20;
+const IFA_F_TENTATIVE: u32 = 0x40;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+const HEADER_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const BUFFER_LEN: usize = 32 * 1024;
+/**
+The kernel answers right away. Without a timeout a lost end of the dump would block the sync forever.
+*/
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/**
+Asks the kernel for the addresses of the interface via rtnetlink, the same way `ip address` does.
+Only addresses with a global scope that are ready to use are returned, in the order the kernel reports them.
+Fails with [`io::ErrorKind::WouldBlock`] if the kernel doesn't finish the dump within the [`RECEIVE_TIMEOUT`].
+*/
+pub(crate) fn global_addresses(interface: &str) -> io::Result<Vec<IpAddr>> {
+    let fd = socket(AddressFamily::NETLINK, SocketType::RAW, None)?;
+    set_socket_timeout(&fd, Timeout::Recv, Some(RECEIVE_TIMEOUT))?;
+    let index = name_to_index(&fd, interface)?;
+    sendto(&fd, &dump_request(), SendFlags::empty(), &SocketAddrNetlink::new(0, 0))?;
+
+    let mut buffer = vec![0; BUFFER_LEN];
+    let mut addresses = Vec::new();
+    loop {
+        let (received, _) = recv(&fd, &mut buffer[..], RecvFlags::empty())?;
+        if parse_messages(&buffer[..received], index, &mut addresses)? {
+            return Ok(addresses);
+        }
+    }
+}
+
+/**
+An `RTM_GETADDR` dump of all addresses. The kernel doesn't filter dumps by interface, so we do that ourselves.
+*/
+fn dump_request() -> Vec<u8> {
+    let mut request = Vec::with_capacity(HEADER_LEN + IFADDRMSG_LEN);
+    request.extend(((HEADER_LEN + IFADDRMSG_LEN) as u32).to_ne_bytes());
+    request.extend(RTM_GETADDR.to_ne_bytes());
+    request.extend((NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    // Sequence number and port ID
+    request.extend(1u32.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes());
+    // ifaddrmsg: family, prefix length, flags, scope and interface index
+    request.extend([0u8; IFADDRMSG_LEN]);
+    request
+}
+
+/**
+Collects the addresses of the interface from one batch of messages. Returns `true` once the dump is complete.
+*/
+fn parse_messages(data: &[u8], index: u32, addresses: &mut Vec<IpAddr>) -> io::Result<bool> {
+    let mut offset = 0;
+    while let (Some(len), Some(kind)) = (read_u32(data, offset), read_u16(data, offset + 4)) {
+        let len = len as usize;
+        let Some(payload) = data.get(offset + HEADER_LEN..offset + len) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Truncated netlink message"));
+        };
+
+        match kind {
+            NLMSG_DONE => return Ok(true),
+            NLMSG_ERROR => match read_u32(payload, 0).map(|errno| errno as i32) {
+                Some(0) => {}
+                Some(errno) => return Err(io::Error::from_raw_os_error(-errno)),
+                None => return Err(io::Error::new(io::ErrorKind::InvalidData, "Truncated netlink error")),
+            },
+            RTM_NEWADDR => addresses.extend(parse_address(payload, index)),
+            _ => {}
+        }
+
+        offset += align(len);
+    }
+
+    Ok(false)
+}
+
+fn parse_address(payload: &[u8], index: u32) -> Option<IpAddr> {
+    let [family, _prefix_len, flags, scope] = read::<4>(payload, 0)?;
+    if read_u32(payload, 4)? != index || scope != RT_SCOPE_UNIVERSE {
+        return None;
+    }
+
+    let mut flags = u32::from(flags);
+    let mut address = None;
+    let mut local = None;
+    for (kind, value) in attributes(payload.get(IFADDRMSG_LEN..)?) {
+        match kind {
+            IFA_ADDRESS => address = parse_ip(family, value),
+            IFA_LOCAL => local = parse_ip(family, value),
+            // The flags in the header only have room for the first 8 bits.
+            IFA_FLAGS => flags = read_u32(value, 0).unwrap_or(flags),
+            _ => {}
+        }
+    }
+
+    if flags & (IFA_F_TENTATIVE | IFA_F_DEPRECATED | IFA_F_DADFAILED) != 0 {
+        return None;
+    }
+
+    // On point-to-point links like PPPoE the address is the one of the peer and only the local one is ours.
+    local.or(address)
+}
+
+fn attributes(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let len = usize::from(read_u16(data, 0)?);
+        let kind = read_u16(data, 2)?;
+        let value = data.get(4..len)?;
+        data = data.get(align(len)..).unwrap_or_default();
+
+        Some((kind, value))
+    })
+}
+
+fn parse_ip(family: u8, value: &[u8]) -> Option<IpAddr> {
+    match family {
+        AF_INET => Some(IpAddr::V4(Ipv4Addr::from(read::<4>(value, 0)?))),
+        AF_INET6 => Some(IpAddr::V6(Ipv6Addr::from(read::<16>(value, 0)?))),
+        _ => None,
+    }
+}
+
+/**
+Netlink messages and attributes are padded to 4 bytes.
+*/
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    read(data, offset).map(u16::from_ne_bytes)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    read(data, offset).map(u32::from_ne_bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::resolver::interface::is_public;
+    use crate::ip::resolver::netlink::{
+        AF_INET, AF_INET6, IFA_ADDRESS, IFA_F_DEPRECATED, IFA_FLAGS, IFA_LOCAL, NLMSG_DONE, NLMSG_ERROR, RTM_NEWADDR,
+        align, parse_messages,
+    };
+    use std::net::IpAddr;
+
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend(((16 + payload.len()) as u32).to_ne_bytes());
+        message.extend(kind.to_ne_bytes());
+        message.extend([0u8; 10]);
+        message.extend(payload);
+        message.resize(align(message.len()), 0);
+        message
+    }
+
+    fn address(family: u8, scope: u8, index: u32, attributes: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut payload = vec![family, 24, 0, scope];
+        payload.extend(index.to_ne_bytes());
+        for (kind, value) in attributes {
+            payload.extend(((4 + value.len()) as u16).to_ne_bytes());
+            payload.extend(kind.to_ne_bytes());
+            payload.extend(value);
+            payload.resize(align(payload.len()), 0);
+        }
+        message(RTM_NEWADDR, &payload)
+    }
+
+    fn ip(address: &str) -> Vec<u8> {
+        match address.parse::<IpAddr>().unwrap() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_global_addresses_of_the_interface() {
+        let data = [
+            address(AF_INET, 0, 2, &[(IFA_ADDRESS, ip("198.51.100.7"))]),
+            // Another interface
+            address(AF_INET, 0, 3, &[(IFA_ADDRESS, ip("198.51.100.8"))]),
+            // Link scope
+            address(AF_INET6, 253, 2, &[(IFA_ADDRESS, ip("fe80::1"))]),
+            address(
+                AF_INET6,
+                0,
+                2,
+                &[(IFA_ADDRESS, ip("2001:db8::dead")), (IFA_FLAGS, IFA_F_DEPRECATED.to_ne_bytes().to_vec())],
+            ),
+            address(AF_INET6, 0, 2, &[(IFA_ADDRESS, ip("2001:db8::1"))]),
+        ]
+        .concat();
+        let mut addresses = Vec::new();
+
+        assert!(!parse_messages(&data, 2, &mut addresses).unwrap());
+        assert_eq!(addresses, vec!["198.51.100.7".parse::<IpAddr>().unwrap(), "2001:db8::1".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_private_addresses_with_a_global_scope_are_not_public() {
+        // The kernel reports all of these with a global scope.
+        let data = [
+            address(AF_INET, 0, 2, &[(IFA_ADDRESS, ip("192.168.1.10"))]),
+            address(AF_INET, 0, 2, &[(IFA_ADDRESS, ip("100.72.1.5"))]),
+            address(AF_INET, 0, 2, &[(IFA_ADDRESS, ip("198.51.100.7"))]),
+            address(AF_INET6, 0, 2, &[(IFA_ADDRESS, ip("fd00::2"))]),
+            address(AF_INET6, 0, 2, &[(IFA_ADDRESS, ip("2001:db8::1"))]),
+        ]
+        .concat();
+        let mut addresses = Vec::new();
+
+        parse_messages(&data, 2, &mut addresses).unwrap();
+        addresses.retain(is_public);
+
+        assert_eq!(addresses, vec!["198.51.100.7".parse::<IpAddr>().unwrap(), "2001:db8::1".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_point_to_point_uses_the_local_address() {
+        let data = address(AF_INET, 0, 5, &[(IFA_ADDRESS, ip("198.51.100.1")), (IFA_LOCAL, ip("198.51.100.7"))]);
+        let mut addresses = Vec::new();
+
+        parse_messages(&data, 5, &mut addresses).unwrap();
+
+        assert_eq!(addresses, vec!["198.51.100.7".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_end_of_dump_and_errors() {
+        let mut addresses = Vec::new();
+
+        assert!(parse_messages(&message(NLMSG_DONE, &[0; 4]), 1, &mut addresses).unwrap());
+        assert!(parse_messages(&message(NLMSG_ERROR, &(-1i32).to_ne_bytes()), 1, &mut addresses).is_err());
+        assert!(!parse_messages(&message(RTM_NEWADDR, &[0; 8])[..4], 1, &mut addresses).unwrap());
+        assert!(parse_messages(&message(RTM_NEWADDR, &[0; 8])[..20], 1, &mut addresses).is_err());
+    }
+}
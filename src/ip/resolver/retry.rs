@@ -0,0 +1,35 @@
+use crate::ip::resolver::IpResolver;
+use crate::ip::resolver::error::ResolverError;
+use crate::retry::RetryPolicy;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/**
+Retries transient failures of the wrapped resolver according to the [`RetryPolicy`].
+*/
+pub(crate) struct RetryingResolver {
+    inner: Box<dyn IpResolver>,
+    policy: RetryPolicy,
+}
+
+impl RetryingResolver {
+    pub(crate) fn new(inner: Box<dyn IpResolver>, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+        }
+    }
+}
+
+impl IpResolver for RetryingResolver {
+    fn get_ipv4(&self) -> Result<Ipv4Addr, ResolverError> {
+        self.policy.run("Resolving the public IPv4 address", || self.inner.get_ipv4())
+    }
+
+    fn get_ipv6(&self) -> Result<Ipv6Addr, ResolverError> {
+        self.policy.run("Resolving the public IPv6 address", || self.inner.get_ipv6())
+    }
+
+    fn invalidate(&self) {
+        self.inner.invalidate();
+    }
+}
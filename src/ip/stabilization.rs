@@ -0,0 +1,148 @@
+use crate::configuration::user::stabilization::StabilizationConfig;
+use crate::ip::cache::{IpCache, Observation};
+use crate::ip::last_known::IpSnapshot;
+use std::time::Duration;
+
+/**
+Decides whether a newly observed public IP is stable enough to be pushed to the DNS records.
+*/
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StabilizationWindow {
+    consecutive_checks: Option<u32>,
+    min_duration: Option<Duration>,
+}
+
+impl StabilizationWindow {
+    /**
+    Only the addresses that differ from the last pushed ones have to be stable.
+    Without last pushed addresses, e.g. right after the start, there is nothing to protect and the change is allowed.
+    */
+    pub(crate) fn allows(&self, last_known: Option<&IpSnapshot>, observed: &IpSnapshot, cache: &IpCache) -> bool {
+        let Some(last_known) = last_known else {
+            return true;
+        };
+
+        let ipv4_stable =
+            observed.ipv4.is_none() || observed.ipv4 == last_known.ipv4 || self.is_stable(cache.ipv4_observation());
+        let ipv6_stable =
+            observed.ipv6.is_none() || observed.ipv6 == last_known.ipv6 || self.is_stable(cache.ipv6_observation());
+
+        ipv4_stable && ipv6_stable
+    }
+
+    /**
+    An address is stable once it was seen on enough consecutive checks or for long enough.
+    */
+    fn is_stable(&self, observation: Option<Observation>) -> bool {
+        if self.consecutive_checks.is_none() && self.min_duration.is_none() {
+            return true;
+        }
+        let Some(observation) = observation else {
+            return false;
+        };
+
+        self.consecutive_checks.is_some_and(|checks| observation.checks >= checks)
+            || self.min_duration.is_some_and(|duration| observation.first_seen.elapsed() >= duration)
+    }
+}
+
+impl From<&StabilizationConfig> for StabilizationWindow {
+    fn from(config: &StabilizationConfig) -> Self {
+        Self {
+            consecutive_checks: config.consecutive_checks,
+            min_duration: config.min_duration,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::ip::cache::IpCache;
+    use crate::ip::last_known::IpSnapshot;
+    use crate::ip::stabilization::StabilizationWindow;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn snapshot(ipv4: &str) -> IpSnapshot {
+        IpSnapshot {
+            ipv4: Some(ipv4.parse().unwrap()),
+            ipv6: None,
+        }
+    }
+
+    #[test]
+    fn test_disabled_window_allows_every_change() {
+        let cache = IpCache::new(60);
+        cache.set_ipv4addr("10.0.0.2".parse::<Ipv4Addr>().unwrap());
+
+        let window = StabilizationWindow::default();
+
+        assert!(window.allows(Some(&snapshot("10.0.0.1")), &snapshot("10.0.0.2"), &cache));
+    }
+
+    #[test]
+    fn test_new_address_needs_consecutive_checks() {
+        let cache = IpCache::new(60);
+        let window = StabilizationWindow {
+            consecutive_checks: Some(3),
+            min_duration: None,
+        };
+        let last_known = snapshot("10.0.0.1");
+        let observed = snapshot("10.0.0.2");
+
+        for _ in 0..2 {
+            cache.set_ipv4addr("10.0.0.2".parse::<Ipv4Addr>().unwrap());
+            assert!(!window.allows(Some(&last_known), &observed, &cache));
+        }
+        cache.set_ipv4addr("10.0.0.2".parse::<Ipv4Addr>().unwrap());
+        assert!(window.allows(Some(&last_known), &observed, &cache));
+    }
+
+    #[test]
+    fn test_flapping_address_is_never_stable() {
+        let cache = IpCache::new(60);
+        let window = StabilizationWindow {
+            consecutive_checks: Some(2),
+            min_duration: None,
+        };
+        let last_known = snapshot("10.0.0.1");
+
+        for _ in 0..3 {
+            cache.set_ipv4addr("10.0.0.2".parse::<Ipv4Addr>().unwrap());
+            assert!(!window.allows(Some(&last_known), &snapshot("10.0.0.2"), &cache));
+            cache.set_ipv4addr("10.0.0.1".parse::<Ipv4Addr>().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_new_address_needs_min_duration() {
+        let cache = IpCache::new(60);
+        cache.set_ipv4addr("10.0.0.2".parse::<Ipv4Addr>().unwrap());
+        let last_known = snapshot("10.0.0.1");
+        let observed = snapshot("10.0.0.2");
+
+        let long = StabilizationWindow {
+            consecutive_checks: None,
+            min_duration: Some(Duration::from_secs(300)),
+        };
+        let short = StabilizationWindow {
+            consecutive_checks: None,
+            min_duration: Some(Duration::ZERO),
+        };
+
+        assert!(!long.allows(Some(&last_known), &observed, &cache));
+        assert!(short.allows(Some(&last_known), &observed, &cache));
+    }
+
+    #[test]
+    fn test_first_observation_is_allowed() {
+        let cache = IpCache::new(60);
+        let window = StabilizationWindow {
+            consecutive_checks: Some(3),
+            min_duration: None,
+        };
+
+        assert!(window.allows(None, &snapshot("10.0.0.1"), &cache));
+    }
+}
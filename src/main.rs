@@ -1,5 +1,6 @@
 use crate::clap_utils::get_styles;
 use crate::commands::cloudflare::{CloudflareCommands, handle_cloudflare_commands};
+use crate::commands::sync::{print_plan, print_report};
 use crate::error::{ApplicationError, print_validation_errors};
 use crate::io_helper::CliWriter;
 use crate::logger::init_logging;
@@ -10,7 +11,7 @@ use configuration::user::config::Config;
 use indicatif::ProgressBar;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 use validator::Validate;
 
 #[cfg(feature = "mimalloc")]
@@ -27,7 +28,14 @@ mod error;
 mod io_helper;
 mod ip;
 mod logger;
+mod parallel;
+mod rate_limit;
+mod reload;
+mod retry;
 mod runner;
+mod scheduler;
+mod shutdown;
+mod state;
 
 pub(crate) type Verbosity = ClapVerbosity<InfoLevel>;
 
@@ -50,6 +58,9 @@ pub(crate) enum Commands {
     Sync {
         #[command(flatten)]
         common: CommonSyncRunArgs,
+
+        #[arg(long, help = "Shows what would be changed without changing any DNS record", default_value_t = false)]
+        dry_run: bool,
     },
 
     #[command(about = "Starts the process that periodically syncs DNS records.")]
@@ -76,13 +87,13 @@ fn main() {
     let writer = Arc::new(init_logging(&args.verbosity, args.debug));
 
     if let Err(err) = run_command(args, &writer) {
-        if let ApplicationError::ValidationErrors(errors) = err {
-            print_validation_errors(&errors);
+        if let ApplicationError::ValidationErrors(errors) = &err {
+            print_validation_errors(errors);
         } else {
             writer.error(format!("{}", err));
         }
 
-        std::process::exit(1);
+        std::process::exit(err.exit_code());
     }
 }
 
@@ -93,36 +104,60 @@ fn run_command(args: CliArgs, writer: &Arc<CliWriter>) -> Result<(), Application
         } => handle_cloudflare_commands(command, writer)?,
         Commands::Sync {
             common,
+            dry_run,
         } => {
             let config = Config::from_file(common.config_file)?;
             config.validate()?;
 
             let records_len = config.get_total_number_of_records();
+            if dry_run {
+                writer.info(format!("Planning the sync of {} DNS records...", records_len));
+
+                let progress_bar = ProgressBar::new(records_len as u64);
+                let mut runner = Runner::new(config, writer);
+                let entries = runner.plan(progress_bar.clone());
+                progress_bar.finish_and_clear();
+                print_plan(&entries);
+
+                let failed = entries.iter().filter(|entry| entry.is_failure()).count();
+                if failed > 0 {
+                    return Err(ApplicationError::SyncFailed {
+                        failed,
+                        total: entries.len(),
+                    });
+                }
+
+                return Ok(());
+            }
+
             writer.info(format!("Syncing DNS {} records...", records_len));
 
             let progress_bar = ProgressBar::new(records_len as u64);
             let mut runner = Runner::new(config, writer);
-            if let Err(err) = runner.sync(progress_bar) {
-                writer.error(format!("{}", err));
-
-                return Ok(());
+            let report = runner.sync(progress_bar.clone())?;
+            progress_bar.finish_and_clear();
+            print_report(&report);
+
+            if report.has_failures() {
+                return Err(ApplicationError::SyncFailed {
+                    failed: report.failed(),
+                    total: report.len(),
+                });
             }
 
-            writer.success(format!("Successfully synced {} record", records_len));
+            writer.success(format!("Successfully synced {} records ({})", records_len, report));
         }
         Commands::Run {
             common,
         } => {
-            let config = Config::from_file(common.config_file)?;
+            let config = Config::from_file(common.config_file.clone())?;
             config.validate()?;
 
             let records_len = config.get_total_number_of_records();
             info!("Running DNS sync for {} records...", records_len);
 
             let runner = Runner::new(config, writer);
-            if let Err(err) = runner.run() {
-                error!("{}", err);
-            }
+            runner.run(common.config_file)?;
         }
     }
 
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+/**
+Runs `work` for every item on up to `concurrency` threads.
+The results are returned in the order of the items, no matter which item finished first.
+*/
+pub(crate) fn map_parallel<T, R, F>(items: Vec<T>, concurrency: usize, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let total = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(total));
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, total.max(1)) {
+            scope.spawn(|| {
+                loop {
+                    #[allow(clippy::expect_used)]
+                    let next = queue.lock().expect("Work queue poisoned").next();
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+
+                    let result = work(item);
+                    #[allow(clippy::expect_used)]
+                    results.lock().expect("Results poisoned").push((index, result));
+                }
+            });
+        }
+    });
+
+    #[allow(clippy::expect_used)]
+    let mut results = results.into_inner().expect("Results poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parallel::map_parallel;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_keep_the_order_of_the_items() {
+        let items: Vec<u64> = (0..20).collect();
+
+        let results = map_parallel(items, 4, |item| {
+            // Later items finish first.
+            sleep(Duration::from_millis(20 - item));
+            item * 2
+        });
+
+        assert_eq!(results, (0..20).map(|item| item * 2).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_concurrency_is_bounded() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        map_parallel((0..12).collect(), 3, |_: i32| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn test_no_items() {
+        let results: Vec<i32> = map_parallel(Vec::new(), 4, |item: i32| item);
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/**
+Spaces out requests evenly, so parallel syncs stay below the rate limit of an API.
+*/
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests_per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /**
+    Blocks until the next request may be sent.
+    */
+    pub(crate) fn acquire(&self) {
+        let wait = {
+            #[allow(clippy::expect_used)]
+            let mut next = self.next.lock().expect("Rate limiter poisoned");
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot - now
+        };
+
+        if !wait.is_zero() {
+            sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limit::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_requests_are_spaced_out() {
+        let limiter = RateLimiter::new(20.0);
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire();
+        }
+
+        // The first request goes out right away, the other four wait 50ms each.
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let limiter = RateLimiter::new(20.0);
+
+        let start = Instant::now();
+        std::thread::scope(|scope| {
+            for _ in 0..5 {
+                scope.spawn(|| limiter.acquire());
+            }
+        });
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
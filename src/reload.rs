@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+/**
+Notices when the config file should be loaded again, either because of a SIGHUP or because it changed on disk.
+*/
+pub(crate) struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    requested: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        let modified = modified_at(&path);
+
+        Self {
+            path,
+            modified,
+            requested: Default::default(),
+        }
+    }
+
+    /**
+    Requests a reload on SIGHUP. There is no such signal on Windows, so only file changes are noticed there.
+    */
+    pub(crate) fn listen(&self) -> Result<(), std::io::Error> {
+        #[cfg(unix)]
+        signal_hook::flag::register(signal_hook::consts::SIGHUP, self.requested.clone())?;
+
+        Ok(())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /**
+    Returns true once per SIGHUP or change of the file.
+    */
+    pub(crate) fn should_reload(&mut self) -> bool {
+        let requested = self.requested.swap(false, Ordering::SeqCst);
+
+        let modified = modified_at(&self.path);
+        let changed = modified.is_some() && modified != self.modified;
+        self.modified = modified;
+
+        requested || changed
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::reload::ConfigWatcher;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn test_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dyncloud-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, "cron = \"0 * * * * *\"").unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_unchanged_file_is_not_reloaded() {
+        let path = test_file("unchanged");
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        assert!(!watcher.should_reload());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_changed_file_is_reloaded_once() {
+        let path = test_file("changed");
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+
+        assert!(watcher.should_reload());
+        assert!(!watcher.should_reload());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_file_is_not_reloaded() {
+        let path = test_file("deleted");
+        let mut watcher = ConfigWatcher::new(path.clone());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(!watcher.should_reload());
+    }
+}
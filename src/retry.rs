@@ -0,0 +1,250 @@
+use crate::configuration::user::retry::RetryConfig;
+use crate::shutdown::Shutdown;
+use std::fmt::Display;
+use std::time::Duration;
+use tracing::warn;
+
+/**
+Tells the retry logic whether an error is worth another attempt.
+*/
+pub(crate) trait TransientError {
+    fn is_transient(&self) -> bool;
+
+    /**
+    The delay the server asked for, e.g. through a `Retry-After` header.
+    */
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    shutdown: Shutdown,
+}
+
+impl RetryPolicy {
+    /**
+    Wakes up from the delay between two attempts when a shutdown is requested and gives up.
+    Otherwise the delay could outlast the [`crate::shutdown::SHUTDOWN_TIMEOUT`].
+    */
+    pub(crate) fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /**
+    Runs the operation until it succeeds, fails with a permanent error, runs out of attempts or a shutdown is requested.
+    */
+    pub(crate) fn run<T, E>(&self, operation: &str, mut f: impl FnMut() -> Result<T, E>) -> Result<T, E>
+    where
+        E: TransientError + Display,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match f() {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            if attempt >= self.max_attempts || !err.is_transient() {
+                return Err(err);
+            }
+
+            let delay = match err.retry_after() {
+                // We rather give up than ignore what the server asked for.
+                Some(delay) if delay > self.max_delay => return Err(err),
+                Some(delay) => delay,
+                None => self.backoff(attempt),
+            };
+
+            warn!(
+                "{} failed (attempt {}/{}): {}. Retrying in {}ms...",
+                operation,
+                attempt,
+                self.max_attempts,
+                err,
+                delay.as_millis()
+            );
+            self.shutdown.sleep(delay);
+            if self.shutdown.is_requested() {
+                return Err(err);
+            }
+            attempt += 1;
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt - 1)).min(self.max_delay);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+
+        let factor = 1.0 + self.jitter * (rand::random::<f64>() * 2.0 - 1.0);
+        delay.mul_f64(factor).min(self.max_delay)
+    }
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts,
+            base_delay: config.base_delay,
+            max_delay: config.max_delay,
+            jitter: config.jitter,
+            shutdown: Shutdown::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retry::{RetryPolicy, TransientError};
+    use crate::shutdown::Shutdown;
+    use std::cell::Cell;
+    use std::fmt::Display;
+    use std::time::{Duration, Instant};
+
+    #[derive(Debug)]
+    struct TestError {
+        transient: bool,
+        retry_after: Option<Duration>,
+    }
+
+    impl Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "test error")
+        }
+    }
+
+    impl TransientError for TestError {
+        fn is_transient(&self) -> bool {
+            self.transient
+        }
+
+        fn retry_after(&self) -> Option<Duration> {
+            self.retry_after
+        }
+    }
+
+    fn create_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            jitter: 0.0,
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    #[test]
+    fn test_retries_transient_errors_until_success() {
+        let attempts = Cell::new(0);
+        let rs = create_policy(3).run("Test", || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(TestError {
+                    transient: true,
+                    retry_after: None,
+                })
+            } else {
+                Ok(())
+            }
+        });
+
+        assert!(rs.is_ok());
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let attempts = Cell::new(0);
+        let rs: Result<(), _> = create_policy(2).run("Test", || {
+            attempts.set(attempts.get() + 1);
+            Err(TestError {
+                transient: true,
+                retry_after: None,
+            })
+        });
+
+        assert!(rs.is_err());
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[test]
+    fn test_does_not_retry_permanent_errors() {
+        let attempts = Cell::new(0);
+        let rs: Result<(), _> = create_policy(5).run("Test", || {
+            attempts.set(attempts.get() + 1);
+            Err(TestError {
+                transient: false,
+                retry_after: None,
+            })
+        });
+
+        assert!(rs.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn test_gives_up_if_retry_after_exceeds_max_delay() {
+        let attempts = Cell::new(0);
+        let rs: Result<(), _> = create_policy(5).run("Test", || {
+            attempts.set(attempts.get() + 1);
+            Err(TestError {
+                transient: true,
+                retry_after: Some(Duration::from_secs(60)),
+            })
+        });
+
+        assert!(rs.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_interrupts_the_delay() {
+        let shutdown = Shutdown::default();
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.0,
+            shutdown: shutdown.clone(),
+        };
+        let attempts = Cell::new(0);
+
+        let start = Instant::now();
+        let rs: Result<(), _> = policy.run("Test", || {
+            attempts.set(attempts.get() + 1);
+            shutdown.request();
+            Err(TestError {
+                transient: true,
+                retry_after: None,
+            })
+        });
+
+        assert!(rs.is_err());
+        assert_eq!(attempts.get(), 1);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            jitter: 0.0,
+            shutdown: Shutdown::default(),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+    }
+}
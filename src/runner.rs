@@ -1,94 +1,1047 @@
-use crate::clock::get_system_timezone_offset;
-use crate::cloudflare_api::build_cloudflare_client;
+use crate::clock::resolve_timezone;
+use crate::cloudflare_api::{CloudflareClient, build_cloudflare_client};
 use crate::configuration::user::config::Config;
-use crate::configuration::user::records::RecordsGroup;
+use crate::configuration::user::providers::Cloudflare;
+use crate::configuration::user::records::{CloudflareRecord as CloudflareConfigRecord, DnsType, RecordsGroup};
 use crate::configuration::user::resolver::Resolver;
+use crate::configuration::user::schedule::Schedule;
+use crate::dns::error::SyncError;
+use crate::dns::plan::PlanEntry;
+use crate::dns::prune::{CloudflarePruner, WantedRecords};
 use crate::dns::record::{CloudflareRecord, SyncableRecord};
+use crate::dns::report::{RecordReport, SyncReport};
+use crate::error::print_validation_errors;
 use crate::io_helper::CliWriter;
 use crate::ip::cache::IpCache;
+use crate::ip::last_known::{IpSnapshot, LastKnownIp};
 use crate::ip::resolver::IpResolver;
-use crate::ip::resolver::ipify::IpifyResolver;
+use crate::ip::resolver::consensus::ConsensusResolver;
+use crate::ip::resolver::error::ResolverError;
+use crate::ip::resolver::fallback::FallbackResolver;
+use crate::ip::resolver::http::HttpResolver;
+use crate::ip::resolver::interface::InterfaceResolver;
+use crate::ip::resolver::retry::RetryingResolver;
+use crate::ip::stabilization::StabilizationWindow;
+use crate::parallel::map_parallel;
+use crate::rate_limit::RateLimiter;
+use crate::reload::ConfigWatcher;
+use crate::retry::RetryPolicy;
+use crate::scheduler::Scheduler;
+use crate::shutdown::Shutdown;
+use crate::state::error::StateError;
+use crate::state::store::{StateStore, cloudflare_state_key};
+use chrono_tz::Tz;
 use indicatif::ProgressBar;
-use job_scheduler_ng::{Cron, Job, JobScheduler};
-use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::thread::sleep;
 use std::time::Duration;
-use tracing::error;
+use tracing::{debug, error, info};
+use validator::Validate;
 
 pub(crate) struct Runner {
+    record_sets: Vec<RecordSet>,
+    state: Arc<StateStore>,
+    schedules: BTreeSet<Schedule>,
+    timezone: Tz,
+    pruners: Vec<CloudflarePruner>,
+    max_deletions: usize,
+    pruned: bool,
+    stabilization: StabilizationWindow,
+    shutdown: Shutdown,
+    writer: Arc<CliWriter>,
+    sync_on_start: bool,
+    concurrency: usize,
+}
+
+/**
+The Cloudflare clients by API token.
+*/
+type CloudflareClients = HashMap<String, Arc<CloudflareClient>>;
+
+/**
+All records of a [`RecordsGroup`] that share the same resolver and therefore the same public IP, and the same schedule.
+*/
+struct RecordSet {
+    key: RecordSetKey,
+    schedule: Schedule,
+    resolver: Arc<Box<dyn IpResolver>>,
+    /**
+    The cache of the group's resolver. Every group has its own, so groups with different resolvers stay independent.
+    */
+    ip_cache: Arc<IpCache>,
     records: Vec<Box<dyn SyncableRecord>>,
-    cron: String,
+    needs_ipv4: bool,
+    needs_ipv6: bool,
+    last_known: LastKnownIp,
+}
+
+/**
+Identifies a record set across config reloads. The configs are compared by their debug output, which covers every
+setting, so a record whose TTL or resolver changed starts over and is synced again.
+*/
+#[derive(Debug, PartialEq, Eq)]
+struct RecordSetKey {
+    resolver: String,
+    zone_id: String,
+    schedule: Schedule,
+    records: Vec<String>,
 }
 
 impl Runner {
     pub(crate) fn new(config: Config, writer: &Arc<CliWriter>) -> Self {
-        let cron = config.cron.clone();
-        let records = Self::build_records(config, writer);
+        let state = Arc::new(Self::open_state_store(&config));
+
+        Self::with_state(config, writer, state, Shutdown::default())
+    }
+
+    fn with_state(config: Config, writer: &Arc<CliWriter>, state: Arc<StateStore>, shutdown: Shutdown) -> Self {
+        state.retain(&Self::state_keys(&config));
+
+        let sync_on_start = config.sync_on_start;
+        let concurrency = config.concurrency;
+        let schedule = config.schedule();
+        let timezone = resolve_timezone(config.timezone.as_deref());
+        let ip_cache_ttl = config.get_total_number_of_records() as u64 * 2;
+        let retry = RetryPolicy::from(&config.retry).with_shutdown(shutdown.clone());
+        let max_deletions = config.prune.max_deletions;
+        let stabilization = StabilizationWindow::from(&config.stabilization);
+        let clients = Self::build_clients(&config);
+        let pruners = match config.prune.enabled {
+            true => Self::build_pruners(&config, &clients, retry.clone(), writer),
+            false => Vec::new(),
+        };
+        let record_sets = Self::build_record_sets(config, &clients, ip_cache_ttl, state.clone(), retry, writer);
+        // The global cron keeps ticking even if every record has its own, so pruning and the state still get handled.
+        let schedules = record_sets.iter().map(|record_set| record_set.schedule.clone()).chain([schedule]).collect();
 
         Self {
-            records,
-            cron,
+            record_sets,
+            state,
+            schedules,
+            timezone,
+            pruners,
+            max_deletions,
+            pruned: false,
+            stabilization,
+            shutdown,
+            writer: writer.clone(),
+            sync_on_start,
+            concurrency,
         }
     }
 
-    pub(crate) fn sync(&mut self, progress_bar: ProgressBar) -> Result<(), anyhow::Error> {
+    /**
+    Syncs all records, even if some of them fail, and returns what happened to each of them.
+    */
+    pub(crate) fn sync(&mut self, progress_bar: ProgressBar) -> Result<SyncReport, StateError> {
+        self.warm_up_ip_cache();
+
         let progress_bar = Some(progress_bar);
-        for record in &mut self.records {
-            record.sync(&progress_bar)?
+        let records = self.record_sets.iter_mut().flat_map(|record_set| record_set.records.iter_mut()).collect();
+        let mut report = SyncReport::default();
+        for reports in map_parallel(records, self.concurrency, |record| record.sync(&progress_bar)) {
+            report.extend(reports);
+        }
+        report.extend(self.prune());
+        self.state.persist()?;
+
+        Ok(report)
+    }
+
+    /**
+    Plans the sync of all records without changing anything.
+    The records are always looked up live, so the plan doesn't depend on what the state file remembers.
+    A record that can't be planned is listed as failed, and deletions beyond `max_deletions` as blocked, like `sync` would.
+    */
+    pub(crate) fn plan(&mut self, progress_bar: ProgressBar) -> Vec<PlanEntry> {
+        self.warm_up_ip_cache();
+
+        let records = self.record_sets.iter_mut().flat_map(|record_set| record_set.records.iter_mut()).collect();
+        let planned = map_parallel(records, self.concurrency, |record| {
+            let mut entries = Vec::new();
+            for dns_type in record.dns_types() {
+                match record.plan_live(&dns_type) {
+                    Ok(changes) => entries.extend(changes.into_iter().map(PlanEntry::Change)),
+                    Err(error) => entries.push(PlanEntry::Failed {
+                        name: record.name().to_string(),
+                        dns_type,
+                        error,
+                    }),
+                }
+                progress_bar.inc(1);
+            }
+            entries
+        });
+        let mut entries: Vec<PlanEntry> = planned.into_iter().flatten().collect();
+
+        let mut deletions = Vec::new();
+        for pruner in &self.pruners {
+            for dns_type in [DnsType::A, DnsType::Aaaa] {
+                match pruner.plan(&dns_type) {
+                    Ok(changes) => deletions.extend(changes),
+                    Err(error) => entries.push(PlanEntry::Failed {
+                        name: format!("zone {}", pruner.zone_id()),
+                        dns_type,
+                        error,
+                    }),
+                }
+            }
+        }
+
+        let count = deletions.len();
+        entries.extend(deletions.into_iter().map(|change| match count > self.max_deletions {
+            true => PlanEntry::Blocked {
+                change,
+                reason: SyncError::TooManyDeletions {
+                    count,
+                    max: self.max_deletions,
+                },
+            },
+            false => PlanEntry::Change(change),
+        }));
+
+        entries
+    }
+
+    /**
+    Resolves the public IP once before the records are synced in parallel, so they don't all look it up at the same time.
+    A failure is reported by the records themselves.
+    */
+    fn warm_up_ip_cache(&self) {
+        for record_set in &self.record_sets {
+            if let Err(err) = record_set.resolve() {
+                debug!("Could not resolve the public IP address in advance: {}", err);
+            }
+        }
+    }
+
+    /**
+    Deletes the owned records that are no longer in the config.
+    Nothing is deleted when this would exceed `max_deletions`, in case the config is broken.
+    */
+    fn prune(&self) -> Vec<RecordReport> {
+        let mut reports = Vec::new();
+        let mut deletions = Vec::new();
+        for pruner in &self.pruners {
+            for dns_type in [DnsType::A, DnsType::Aaaa] {
+                match pruner.plan(&dns_type) {
+                    Ok(changes) => deletions.extend(changes.into_iter().map(|change| (pruner, change))),
+                    Err(err) => reports.push(RecordReport {
+                        name: format!("zone {}", pruner.zone_id()),
+                        dns_type,
+                        result: Err(err),
+                    }),
+                }
+            }
+        }
+
+        let count = deletions.len();
+        for (pruner, change) in deletions {
+            let name = change.name.clone();
+            let dns_type = change.dns_type;
+            let result = match count > self.max_deletions {
+                true => Err(SyncError::TooManyDeletions {
+                    count,
+                    max: self.max_deletions,
+                }),
+                false => pruner.apply(change),
+            };
+            reports.push(RecordReport {
+                name,
+                dns_type,
+                result,
+            });
+        }
+
+        reports
+    }
+
+    /**
+    Runs the scheduled syncs until a termination signal arrives.
+    A sync that is running at that time stops after the current record, then the state is written one last time.
+    The config file is loaded again on SIGHUP or when it changes on disk.
+    */
+    pub(crate) fn run(self, config_file: PathBuf) -> Result<(), anyhow::Error> {
+        let shutdown = self.shutdown.clone();
+        shutdown.listen()?;
+        shutdown.spawn_watchdog();
+
+        let watcher = ConfigWatcher::new(config_file);
+        watcher.listen()?;
+
+        self.run_until_shutdown(watcher)
+    }
+
+    /**
+    The loop of [`Self::run`], without the signal handlers and the watchdog.
+    */
+    fn run_until_shutdown(mut self, mut watcher: ConfigWatcher) -> Result<(), anyhow::Error> {
+        let shutdown = self.shutdown.clone();
+
+        // After a reboot the IP has most likely changed, so we don't wait for the first scheduled check.
+        if self.sync_on_start {
+            info!("Syncing all records on start...");
+            let report = self.sync_changed_records(&self.schedules.clone());
+            info!("Initial sync finished: {}", report);
+        }
+
+        let mut scheduler = Self::build_scheduler(&self.schedules, self.timezone)?;
+
+        while !shutdown.is_requested() {
+            if watcher.should_reload()
+                && let Some(runner) = self.reload(watcher.path())
+            {
+                match Self::build_scheduler(&runner.schedules, runner.timezone) {
+                    Ok(new_scheduler) => {
+                        scheduler = new_scheduler;
+                        self = runner;
+                        info!("Reloaded the config from {}", watcher.path().display());
+                    }
+                    Err(err) => error!("Keeping the current config, because the new cron is invalid: {}", err),
+                }
+            }
+
+            let due_now = scheduler.due();
+            // Schedules that fire at the same time are synced together, so they share the resolved IP.
+            if !due_now.is_empty() {
+                self.sync_changed_records(&due_now);
+            }
+
+            shutdown.sleep(Duration::from_millis(1_000));
         }
 
+        info!("Shutting down...");
+        self.state.persist()?;
+
         Ok(())
     }
 
-    pub(crate) fn run(self) -> Result<(), anyhow::Error> {
-        let mut scheduler = JobScheduler::new();
-        scheduler.set_timezone(get_system_timezone_offset());
+    /**
+    Registers one job per distinct schedule.
+    */
+    fn build_scheduler(schedules: &BTreeSet<Schedule>, timezone: Tz) -> Result<Scheduler, anyhow::Error> {
+        info!("Evaluating the schedules in the timezone {}", timezone);
+
+        let mut scheduler = Scheduler::new(timezone);
+        for schedule in schedules {
+            scheduler.add(schedule)?;
+        }
+
+        Ok(scheduler)
+    }
+
+    /**
+    Builds a new runner from the config file. An invalid config is rejected and the current runner keeps running.
+    The state store is handed over, so records that are still configured keep their known IDs.
+    */
+    fn reload(&self, config_file: &Path) -> Option<Runner> {
+        info!("Reloading the config from {}...", config_file.display());
+
+        let config = match Config::from_file(config_file.to_path_buf()) {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Keeping the current config, because the new one could not be loaded: {}", err);
+                return None;
+            }
+        };
+        if let Err(errors) = config.validate() {
+            print_validation_errors(&errors);
+            error!("Keeping the current config, because the new one is invalid.");
+            return None;
+        }
+
+        let state = match config.state_file.as_deref() == self.state.path() {
+            true => self.state.clone(),
+            false => {
+                if let Err(err) = self.state.persist() {
+                    error!("An error occurred while writing the state file: {}", err);
+                }
+                Arc::new(Self::open_state_store(&config))
+            }
+        };
+
+        let mut runner = Self::with_state(config, &self.writer, state, self.shutdown.clone());
+        runner.carry_over(self);
+
+        Some(runner)
+    }
+
+    /**
+    Keeps the last pushed addresses and the stabilization progress of the record sets that didn't change,
+    so a reload neither syncs them again nor restarts a pending stabilization window.
+    */
+    fn carry_over(&mut self, previous: &Runner) {
+        for record_set in &mut self.record_sets {
+            let Some(old) = previous.record_sets.iter().find(|old| old.key == record_set.key) else {
+                continue;
+            };
+            record_set.last_known = old.last_known.clone();
+            record_set.ip_cache.take_over(&old.ip_cache);
+        }
+    }
+
+    /**
+    Resolves the public IP once and only syncs the records whose addresses changed since the last successful sync.
+    */
+    fn sync_changed_records(&mut self, due: &BTreeSet<Schedule>) -> SyncReport {
+        for record_set in &self.record_sets {
+            record_set.ip_cache.invalidate();
+            record_set.resolver.invalidate();
+        }
+
+        let mut total = SyncReport::default();
+        for record_set in &mut self.record_sets {
+            if self.shutdown.is_requested() {
+                break;
+            }
+            if !due.contains(&record_set.schedule) {
+                continue;
+            }
 
-        let cron: Cron = Cron::from_str(&self.cron)?;
-        for mut record in self.records {
-            scheduler.add(Job::new(cron.clone(), move || {
-                if let Err(err) = record.sync(&None) {
-                    error!("An error occurred while syncing records: {}", err);
+            let observed = match record_set.resolve() {
+                Ok(observed) => observed,
+                Err(err) => {
+                    error!("An error occurred while resolving the public IP address: {}", err);
+                    continue;
                 }
-            }));
+            };
+
+            let ip_changed = record_set.last_known.has_changed(&observed);
+            if ip_changed
+                && !self.stabilization.allows(record_set.last_known.snapshot(), &observed, &record_set.ip_cache)
+            {
+                info!("Public IP address changed to {}. Waiting for it to stabilize before syncing.", observed);
+                continue;
+            }
+
+            // Without a new IP only the records that have to be written again after `force_update_after` are synced.
+            let records: Vec<&mut Box<dyn SyncableRecord>> =
+                record_set.records.iter_mut().filter(|record| ip_changed || record.is_refresh_due()).collect();
+            if records.is_empty() {
+                debug!("Public IP address is still {}. Nothing to sync.", observed);
+                continue;
+            }
+
+            match ip_changed {
+                true => info!("Public IP address is now {}. Syncing {} records...", observed, records.len()),
+                false => info!("Public IP address is still {}. Refreshing {} records...", observed, records.len()),
+            }
+            let results = map_parallel(records, self.concurrency, |record| match self.shutdown.is_requested() {
+                true => None,
+                false => Some(record.sync(&None)),
+            });
+            let cancelled = results.iter().any(Option::is_none);
+            let mut report = SyncReport::default();
+            for reports in results.into_iter().flatten() {
+                report.extend(reports);
+            }
+
+            log_failures(&report);
+            info!("Sync finished: {}", report);
+
+            // Failed or skipped records are retried on the next check.
+            if !report.has_failures() && !cancelled {
+                record_set.last_known.remember(observed);
+            }
+            total.extend(report.records);
         }
 
-        loop {
-            scheduler.tick();
+        // A successful prune is enough until the config is reloaded.
+        if !self.pruners.is_empty() && !self.pruned && !self.shutdown.is_requested() {
+            let mut report = SyncReport::default();
+            report.extend(self.prune());
+            log_failures(&report);
+            self.pruned = !report.has_failures();
+            total.extend(report.records);
+        }
 
-            sleep(Duration::from_millis(1_000))
+        if let Err(err) = self.state.persist() {
+            error!("An error occurred while writing the state file: {}", err);
         }
+
+        total
     }
 
-    fn build_records(config: Config, writer: &Arc<CliWriter>) -> Vec<Box<dyn SyncableRecord>> {
-        let mut cf_records: Vec<Box<dyn SyncableRecord>> = Vec::new();
+    fn open_state_store(config: &Config) -> StateStore {
+        match &config.state_file {
+            Some(path) => StateStore::load(path.clone()),
+            None => StateStore::in_memory(),
+        }
+    }
 
-        let ip_cache = Arc::new(IpCache::new(config.get_total_number_of_records() as u64 * 2));
+    /**
+    The state keys of all configured records. Everything else in the state belongs to records that were removed.
+    */
+    fn state_keys(config: &Config) -> HashSet<String> {
+        config
+            .records
+            .iter()
+            .filter_map(|group| group.providers.cloudflare.as_ref().map(|provider| (provider, &group.cloudflare)))
+            .flat_map(|(provider, records)| {
+                records.iter().flat_map(move |record| {
+                    record.basic_record.dns_type.iter().map(move |dns_type| {
+                        cloudflare_state_key(&provider.zone_id, &record.basic_record.name, dns_type)
+                    })
+                })
+            })
+            .collect()
+    }
 
-        for group in config.records {
-            let resolver = Self::build_resolver(&group, ip_cache.clone());
-            if let Some(provider) = group.providers.cloudflare {
-                let provider = Arc::new(provider);
-                let client = Arc::new(build_cloudflare_client(provider.auth_token.clone()));
-                for record in group.cloudflare {
-                    cf_records.push(Box::new(CloudflareRecord::new(
-                        resolver.clone(),
-                        client.clone(),
-                        provider.clone(),
-                        record,
-                        writer.clone(),
-                    )));
+    /**
+    One client per API token, so every record and zone of a token counts towards the same rate limit.
+    When groups configure different limits for the same token, the lowest one wins.
+    */
+    fn build_clients(config: &Config) -> CloudflareClients {
+        let mut limits: HashMap<&str, f64> = HashMap::new();
+        for provider in config.records.iter().filter_map(|group| group.providers.cloudflare.as_ref()) {
+            let limit = limits.entry(provider.auth_token.as_str()).or_insert(provider.requests_per_second);
+            *limit = limit.min(provider.requests_per_second);
+        }
+
+        limits
+            .into_iter()
+            .map(|(auth_token, requests_per_second)| {
+                let client = CloudflareClient::new(
+                    build_cloudflare_client(auth_token.to_string()),
+                    RateLimiter::new(requests_per_second),
+                );
+                (auth_token.to_string(), Arc::new(client))
+            })
+            .collect()
+    }
+
+    /**
+    Builds one pruner per zone, which knows all records configured for that zone, even across groups.
+    */
+    fn build_pruners(
+        config: &Config,
+        clients: &CloudflareClients,
+        retry: RetryPolicy,
+        writer: &Arc<CliWriter>,
+    ) -> Vec<CloudflarePruner> {
+        // Validation makes sure there is one when pruning is enabled.
+        let Some(instance) = &config.prune.instance else {
+            return Vec::new();
+        };
+        let mut zones: BTreeMap<&str, (&Cloudflare, WantedRecords)> = BTreeMap::new();
+        for group in &config.records {
+            let Some(provider) = &group.providers.cloudflare else {
+                continue;
+            };
+
+            let (_, wanted) =
+                zones.entry(provider.zone_id.as_str()).or_insert_with(|| (provider, WantedRecords::new()));
+            for record in &group.cloudflare {
+                for dns_type in &record.basic_record.dns_type {
+                    wanted.insert((record.basic_record.name.clone(), *dns_type));
                 }
             }
         }
 
-        cf_records
+        zones
+            .into_values()
+            .filter_map(|(provider, wanted)| Some((clients.get(&provider.auth_token)?, provider, wanted)))
+            .map(|(client, provider, wanted)| {
+                CloudflarePruner::new(
+                    client.clone(),
+                    Arc::new(provider.clone()),
+                    instance.clone(),
+                    wanted,
+                    writer.clone(),
+                    retry.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn build_record_sets(
+        config: Config,
+        clients: &CloudflareClients,
+        ip_cache_ttl: u64,
+        state: Arc<StateStore>,
+        retry: RetryPolicy,
+        writer: &Arc<CliWriter>,
+    ) -> Vec<RecordSet> {
+        let mut record_sets = Vec::new();
+        let global_schedule = config.schedule();
+
+        for group in config.records {
+            let resolver_key = format!("{:?}", group.resolver);
+            let ip_cache = Arc::new(IpCache::new(ip_cache_ttl));
+            let resolver = Self::build_resolver(&group, ip_cache.clone(), retry.clone());
+            let Some(provider) = group.providers.cloudflare else {
+                continue;
+            };
+            let Some(client) = clients.get(&provider.auth_token).cloned() else {
+                continue;
+            };
+            let provider = Arc::new(provider);
+
+            let mut schedules: BTreeMap<Schedule, Vec<CloudflareConfigRecord>> = BTreeMap::new();
+            for mut record in group.cloudflare {
+                record.basic_record.force_update_after =
+                    record.basic_record.force_update_after.or(group.force_update_after);
+                let schedule = record.basic_record.effective_schedule(group.cron.as_deref(), &global_schedule);
+                schedules.entry(schedule).or_default().push(record);
+            }
+
+            for (schedule, records) in schedules {
+                let dns_types: Vec<DnsType> =
+                    records.iter().flat_map(|record| record.basic_record.dns_type.clone()).collect();
+                let key = RecordSetKey {
+                    resolver: resolver_key.clone(),
+                    zone_id: provider.zone_id.clone(),
+                    schedule: schedule.clone(),
+                    records: records.iter().map(|record| format!("{:?}", record)).collect(),
+                };
+                let records: Vec<Box<dyn SyncableRecord>> = records
+                    .into_iter()
+                    .map(|record| -> Box<dyn SyncableRecord> {
+                        Box::new(
+                            CloudflareRecord::new(
+                                resolver.clone(),
+                                client.clone(),
+                                provider.clone(),
+                                record,
+                                writer.clone(),
+                                state.clone(),
+                                retry.clone(),
+                            )
+                            .with_instance(config.prune.instance.as_deref()),
+                        )
+                    })
+                    .collect();
+
+                record_sets.push(RecordSet {
+                    key,
+                    schedule,
+                    resolver: resolver.clone(),
+                    ip_cache: ip_cache.clone(),
+                    records,
+                    needs_ipv4: dns_types.contains(&DnsType::A),
+                    needs_ipv6: dns_types.contains(&DnsType::Aaaa),
+                    last_known: LastKnownIp::default(),
+                });
+            }
+        }
+
+        record_sets
+    }
+
+    fn build_resolver(
+        records_group: &RecordsGroup,
+        cache: Arc<IpCache>,
+        retry: RetryPolicy,
+    ) -> Arc<Box<dyn IpResolver>> {
+        let resolver = Self::build_ip_resolver(&records_group.resolver, cache);
+
+        Arc::new(Box::new(RetryingResolver::new(resolver, retry)))
+    }
+
+    fn build_ip_resolver(resolver: &Resolver, cache: Arc<IpCache>) -> Box<dyn IpResolver> {
+        match resolver {
+            Resolver::Ipfiy => Box::new(HttpResolver::ipify(cache)),
+            Resolver::Icanhazip => Box::new(HttpResolver::icanhazip(cache)),
+            Resolver::IfconfigCo => Box::new(HttpResolver::ifconfig_co(cache)),
+            Resolver::IfconfigMe => Box::new(HttpResolver::ifconfig_me(cache)),
+            Resolver::Ipinfo => Box::new(HttpResolver::ipinfo(cache)),
+            Resolver::AwsCheckip => Box::new(HttpResolver::aws_checkip(cache)),
+            Resolver::CloudflareTrace => Box::new(HttpResolver::cloudflare_trace(cache)),
+            Resolver::Http(config) => Box::new(HttpResolver::custom(config, cache)),
+            Resolver::Fallback(config) => Box::new(FallbackResolver::new(
+                config
+                    .resolvers
+                    .iter()
+                    .map(|resolver| (resolver.to_string(), Self::build_ip_resolver(resolver, cache.clone())))
+                    .collect(),
+                config.max_failures,
+                config.cooldown,
+            )),
+            // Every resolver gets its own cache, so the answers are independent of each other.
+            Resolver::Consensus(config) => Box::new(ConsensusResolver::new(
+                cache,
+                config
+                    .resolvers
+                    .iter()
+                    .map(|resolver| {
+                        (resolver.to_string(), Self::build_ip_resolver(resolver, Arc::new(IpCache::new(0))))
+                    })
+                    .collect(),
+                config.quorum,
+            )),
+            Resolver::Interface {
+                name,
+            } => Box::new(InterfaceResolver::new(name, cache)),
+        }
+    }
+}
+
+impl RecordSet {
+    fn resolve(&self) -> Result<IpSnapshot, ResolverError> {
+        Ok(IpSnapshot {
+            ipv4: if self.needs_ipv4 {
+                Some(self.resolver.get_ipv4()?)
+            } else {
+                None
+            },
+            ipv6: if self.needs_ipv6 {
+                Some(self.resolver.get_ipv6()?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+fn log_failures(report: &SyncReport) {
+    for failure in report.failures() {
+        if let Err(err) = &failure.result {
+            error!("An error occurred while syncing the {} record {}: {}", failure.dns_type, failure.name, err);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::Verbosity;
+    use crate::cloudflare_api::CloudflareClient;
+    use crate::configuration::user::config::Config;
+    use crate::configuration::user::providers::Cloudflare;
+    use crate::configuration::user::records::DnsType;
+    use crate::configuration::user::retry::RetryConfig;
+    use crate::dns::error::SyncError;
+    use crate::dns::plan::{PlanEntry, PlannedChange};
+    use crate::dns::prune::CloudflarePruner;
+    use crate::dns::record::SyncableRecord;
+    use crate::dns::report::SyncOutcome;
+    use crate::io_helper::CliWriter;
+    use crate::ip::last_known::IpSnapshot;
+    use crate::rate_limit::RateLimiter;
+    use crate::reload::ConfigWatcher;
+    use crate::retry::RetryPolicy;
+    use crate::runner::Runner;
+    use crate::shutdown::Shutdown;
+    use crate::state::store::StateStore;
+    use cloudflare::framework::Environment;
+    use cloudflare::framework::auth::Credentials;
+    use cloudflare::framework::client::ClientConfig;
+    use cloudflare::framework::client::blocking_api::HttpApiClient;
+    use indicatif::ProgressBar;
+    use mockito::{Matcher, Server, ServerGuard};
+    use serde_json::json;
+    use std::collections::HashSet;
+    use std::net::Ipv4Addr;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    const TWO_GROUPS: &str = r#"
+        cron = "0 * * * * *"
+
+        [[domains]]
+        resolver = "Ipfiy"
+        [domains.config.cloudflare]
+        auth_token = "token"
+        zone_id = "zone"
+        [[domains.cloudflare]]
+        name = "ipify.example.internal"
+        ttl = 1
+        type = ["A"]
+        proxied = false
+
+        [[domains]]
+        resolver = { Interface = { name = "dyncloud-none0" } }
+        [domains.config.cloudflare]
+        auth_token = "token"
+        zone_id = "zone"
+        [[domains.cloudflare]]
+        name = "interface.example.internal"
+        ttl = 1
+        type = ["A"]
+        proxied = false
+    "#;
+
+    fn runner(config: &str) -> Runner {
+        let config: Config = toml::from_str(config).unwrap();
+        let writer = Arc::new(CliWriter::new(&Verbosity::default()));
+
+        Runner::with_state(config, &writer, Arc::new(StateStore::in_memory()), Shutdown::default())
+    }
+
+    #[test]
+    fn test_groups_with_different_resolvers_dont_share_the_ip_cache() {
+        let runner = runner(TWO_GROUPS);
+        let [ipify, interface] = runner.record_sets.as_slice() else {
+            panic!("Expected one record set per group");
+        };
+
+        ipify.ip_cache.set_ipv4addr(Ipv4Addr::new(203, 0, 113, 1));
+
+        assert!(!Arc::ptr_eq(&ipify.ip_cache, &interface.ip_cache));
+        assert_eq!(ipify.resolver.get_ipv4().unwrap(), Ipv4Addr::new(203, 0, 113, 1));
+        // The interface doesn't exist, so the address of the other group must not be returned from the cache.
+        assert!(interface.resolver.get_ipv4().is_err());
+    }
+
+    #[test]
+    fn test_reload_keeps_the_state_of_unchanged_record_sets() {
+        let mut runner = runner(TWO_GROUPS);
+        let observed = IpSnapshot {
+            ipv4: Some(Ipv4Addr::new(203, 0, 113, 1)),
+            ipv6: None,
+        };
+        for record_set in &mut runner.record_sets {
+            record_set.ip_cache.set_ipv4addr(Ipv4Addr::new(203, 0, 113, 1));
+            record_set.ip_cache.set_ipv4addr(Ipv4Addr::new(203, 0, 113, 1));
+            record_set.last_known.remember(observed);
+        }
+
+        let path = std::env::temp_dir().join(format!("dyncloud-reload-state-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            TWO_GROUPS.replace(
+                "name = \"interface.example.internal\"\n        ttl = 1",
+                "name = \"interface.example.internal\"\n        ttl = 120",
+            ),
+        )
+        .unwrap();
+        let reloaded = runner.reload(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let [unchanged, changed] = reloaded.record_sets.as_slice() else {
+            panic!("Expected one record set per group");
+        };
+        assert_eq!(unchanged.last_known.snapshot(), Some(&observed));
+        assert_eq!(unchanged.ip_cache.ipv4_observation().unwrap().checks, 2);
+        // The TTL of the record changed, so it has to be synced again.
+        assert_eq!(changed.last_known.snapshot(), None);
+        assert!(changed.ip_cache.ipv4_observation().is_none());
+    }
+
+    /**
+    Counts how often it was synced instead of talking to Cloudflare.
+    */
+    struct CountingRecord(Arc<AtomicU32>);
+
+    impl SyncableRecord for CountingRecord {
+        fn dns_types(&self) -> Vec<DnsType> {
+            vec![DnsType::A]
+        }
+
+        fn plan(&mut self, _dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Vec::new())
+        }
+
+        fn plan_live(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+            self.plan(dns_type)
+        }
+
+        fn apply(&mut self, _change: PlannedChange) -> Vec<Result<SyncOutcome, SyncError>> {
+            vec![Ok(SyncOutcome::Unchanged)]
+        }
+
+        fn name(&self) -> &str {
+            "counting.example.internal"
+        }
+    }
+
+    /**
+    Runs the runner for a moment with a cron that only fires once a year and returns how often the record was synced.
+    */
+    fn syncs_on_start(sync_on_start: bool) -> u32 {
+        let (ip_server, _ip_mock) = ip_server();
+        let mut runner = runner(&format!(
+            r#"
+            cron = "0 0 0 1 1 *"
+            sync_on_start = {}
+
+            [[domains]]
+            resolver = {{ Http = {{ ipv4_url = "{}/ipv4" }} }}
+            [domains.config.cloudflare]
+            auth_token = "token"
+            zone_id = "zone"
+            [[domains.cloudflare]]
+            name = "counting.example.internal"
+            ttl = 1
+            type = ["A"]
+            proxied = false
+            "#,
+            sync_on_start,
+            ip_server.url()
+        ));
+        let syncs = Arc::new(AtomicU32::new(0));
+        runner.record_sets[0].records = vec![Box::new(CountingRecord(syncs.clone()))];
+
+        let shutdown = runner.shutdown.clone();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(1_500));
+            shutdown.request();
+        });
+        runner.run_until_shutdown(ConfigWatcher::new(PathBuf::from("does-not-exist.toml"))).unwrap();
+        stopper.join().unwrap();
+
+        syncs.load(Ordering::SeqCst)
+    }
+
+    fn ip_server() -> (ServerGuard, mockito::Mock) {
+        let mut server = Server::new();
+        let mock = server.mock("GET", "/ipv4").with_status(200).with_body("203.0.113.1").create();
+
+        (server, mock)
+    }
+
+    /**
+    Fails to plan, like a record with duplicates and `on_duplicate = "fail"`.
+    */
+    struct FailingRecord;
+
+    impl SyncableRecord for FailingRecord {
+        fn dns_types(&self) -> Vec<DnsType> {
+            vec![DnsType::A]
+        }
+
+        fn plan(&mut self, _dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+            Err(SyncError::RateLimited("too many requests".to_string()))
+        }
+
+        fn plan_live(&mut self, dns_type: &DnsType) -> Result<Vec<PlannedChange>, SyncError> {
+            self.plan(dns_type)
+        }
+
+        fn apply(&mut self, _change: PlannedChange) -> Vec<Result<SyncOutcome, SyncError>> {
+            vec![Ok(SyncOutcome::Unchanged)]
+        }
+
+        fn name(&self) -> &str {
+            "failing.example.internal"
+        }
+    }
+
+    fn http_resolver_runner(ip_server: &ServerGuard) -> Runner {
+        runner(&format!(
+            r#"
+            cron = "0 0 0 1 1 *"
+
+            [[domains]]
+            resolver = {{ Http = {{ ipv4_url = "{}/ipv4" }} }}
+            [domains.config.cloudflare]
+            auth_token = "token"
+            zone_id = "zone"
+            [[domains.cloudflare]]
+            name = "counting.example.internal"
+            ttl = 1
+            type = ["A"]
+            proxied = false
+            "#,
+            ip_server.url()
+        ))
     }
 
-    fn build_resolver(records_group: &RecordsGroup, cache: Arc<IpCache>) -> Arc<Box<dyn IpResolver>> {
-        match records_group.resolver {
-            Resolver::Ipfiy => Arc::new(Box::new(IpifyResolver::from_ip_cache(cache))),
+    #[test]
+    fn test_plan_lists_a_failing_record_and_plans_the_rest() {
+        let (ip_server, _ip_mock) = ip_server();
+        let mut runner = http_resolver_runner(&ip_server);
+        let plans = Arc::new(AtomicU32::new(0));
+        runner.record_sets[0].records = vec![Box::new(FailingRecord), Box::new(CountingRecord(plans.clone()))];
+
+        let entries = runner.plan(ProgressBar::hidden());
+
+        assert_eq!(plans.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            entries.as_slice(),
+            [PlanEntry::Failed { name, dns_type: DnsType::A, error: SyncError::RateLimited(_) }]
+                if name == "failing.example.internal"
+        ));
+    }
+
+    #[test]
+    fn test_plan_blocks_deletions_beyond_max_deletions() {
+        let (ip_server, _ip_mock) = ip_server();
+        let mut cloudflare = Server::new();
+        let list_mock = cloudflare
+            .mock("GET", Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({
+                  "result": [{
+                    "id": "removed",
+                    "name": "removed.example.internal",
+                    "type": "A",
+                    "content": "203.0.113.1",
+                    "proxiable": true,
+                    "proxied": false,
+                    "ttl": 60,
+                    "settings": {},
+                    "meta": {},
+                    "comment": "managed-by=dyncloud dyncloud-instance=home",
+                    "tags": [],
+                    "created_on": "2024-12-27T16:33:47.054786Z",
+                    "modified_on": "2025-10-08T20:48:29.15931Z"
+                  }],
+                  "success": true,
+                  "errors": [],
+                  "messages": []
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create();
+        let mut runner = http_resolver_runner(&ip_server);
+        runner.record_sets.clear();
+        runner.pruners = vec![test_pruner(&cloudflare)];
+        runner.max_deletions = 1;
+
+        let entries = runner.plan(ProgressBar::hidden());
+
+        // One record is found per type, so the prune would delete two records.
+        assert_eq!(entries.len(), 2);
+        for entry in &entries {
+            assert!(matches!(
+                entry,
+                PlanEntry::Blocked { change, reason: SyncError::TooManyDeletions { count: 2, max: 1 } }
+                    if change.name == "removed.example.internal"
+            ));
         }
+
+        list_mock.assert();
+    }
+
+    fn test_pruner(server: &ServerGuard) -> CloudflarePruner {
+        let http_client = HttpApiClient::new(
+            Credentials::UserAuthToken {
+                token: "token".to_string(),
+            },
+            ClientConfig::default(),
+            Environment::Custom(server.url()),
+        )
+        .unwrap();
+
+        CloudflarePruner::new(
+            Arc::new(CloudflareClient::new(http_client, RateLimiter::new(100.0))),
+            Arc::new(Cloudflare {
+                auth_token: "token".to_string(),
+                zone_id: "zone".to_string(),
+                requests_per_second: 4.0,
+            }),
+            "home".to_string(),
+            HashSet::new(),
+            Arc::new(CliWriter::new(&Verbosity::default())),
+            RetryPolicy::from(&RetryConfig::default()),
+        )
+    }
+
+    #[test]
+    fn test_sync_on_start_syncs_right_away() {
+        assert_eq!(syncs_on_start(true), 1);
+    }
+
+    #[test]
+    fn test_without_sync_on_start_the_schedule_is_awaited() {
+        assert_eq!(syncs_on_start(false), 0);
     }
 }
@@ -0,0 +1,212 @@
+use crate::configuration::user::schedule::Schedule;
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use croner::errors::CronError;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::error;
+
+/**
+Tells which schedules are due.
+Crons are evaluated in a real timezone, so they follow daylight saving time changes of long-running processes.
+*/
+pub(crate) struct Scheduler {
+    timezone: Tz,
+    jobs: Vec<Job>,
+}
+
+struct Job {
+    schedule: Schedule,
+    trigger: Trigger,
+    next: Option<DateTime<Tz>>,
+}
+
+enum Trigger {
+    Cron(Box<Cron>),
+    Interval {
+        every: Duration,
+        splay: Duration,
+    },
+}
+
+impl Trigger {
+    fn next_after(&self, now: &DateTime<Tz>) -> Result<DateTime<Tz>, String> {
+        match self {
+            Trigger::Cron(cron) => cron.find_next_occurrence(now, false).map_err(|err| err.to_string()),
+            Trigger::Interval {
+                every,
+                splay,
+            } => {
+                let delay = *every + splay.mul_f64(rand::random::<f64>());
+                TimeDelta::from_std(delay)
+                    .ok()
+                    .and_then(|delay| now.checked_add_signed(delay))
+                    .ok_or_else(|| format!("The interval of {:?} is too long", delay))
+            }
+        }
+    }
+}
+
+impl Scheduler {
+    pub(crate) fn new(timezone: Tz) -> Self {
+        Self {
+            timezone,
+            jobs: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, schedule: &Schedule) -> Result<(), CronError> {
+        self.add_at(schedule, Utc::now())
+    }
+
+    /**
+    Returns the schedules that fired since the last call.
+    */
+    pub(crate) fn due(&mut self) -> BTreeSet<Schedule> {
+        self.due_at(Utc::now())
+    }
+
+    fn add_at(&mut self, schedule: &Schedule, now: DateTime<Utc>) -> Result<(), CronError> {
+        let trigger = match schedule {
+            Schedule::Cron(cron) => Trigger::Cron(Box::new(Cron::from_str(cron)?)),
+            Schedule::Interval {
+                every,
+                splay,
+            } => Trigger::Interval {
+                every: *every,
+                splay: *splay,
+            },
+        };
+        let next = Self::next_occurrence(schedule, &trigger, &now.with_timezone(&self.timezone));
+        self.jobs.push(Job {
+            schedule: schedule.clone(),
+            trigger,
+            next,
+        });
+
+        Ok(())
+    }
+
+    fn due_at(&mut self, now: DateTime<Utc>) -> BTreeSet<Schedule> {
+        let now = now.with_timezone(&self.timezone);
+        let mut due = BTreeSet::new();
+        for job in &mut self.jobs {
+            let Some(next) = job.next else {
+                continue;
+            };
+            if next > now {
+                continue;
+            }
+
+            due.insert(job.schedule.clone());
+            // Missed occurrences, e.g. while a long sync was running, are only run once.
+            job.next = Self::next_occurrence(&job.schedule, &job.trigger, &now);
+        }
+
+        due
+    }
+
+    fn next_occurrence(schedule: &Schedule, trigger: &Trigger, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match trigger.next_after(now) {
+            Ok(next) => Some(next),
+            Err(err) => {
+                error!("The schedule {} will not fire again: {}", schedule, err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::schedule::Schedule;
+    use crate::scheduler::Scheduler;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    fn cron(expression: &str) -> Schedule {
+        Schedule::Cron(expression.to_string())
+    }
+
+    fn utc(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_job_fires_once_per_occurrence() {
+        let start = utc("2026-01-01T00:00:30Z");
+        let mut scheduler = Scheduler::new(Tz::UTC);
+        scheduler.add_at(&cron("0 * * * * *"), start).unwrap();
+
+        assert!(scheduler.due_at(start + Duration::seconds(10)).is_empty());
+        assert!(scheduler.due_at(start + Duration::seconds(30)).contains(&cron("0 * * * * *")));
+        assert!(scheduler.due_at(start + Duration::seconds(31)).is_empty());
+    }
+
+    #[test]
+    fn test_only_due_schedules_are_returned() {
+        let start = utc("2026-01-01T00:00:30Z");
+        let mut scheduler = Scheduler::new(Tz::UTC);
+        scheduler.add_at(&cron("0 * * * * *"), start).unwrap();
+        scheduler.add_at(&cron("0 0 * * * *"), start).unwrap();
+
+        let due = scheduler.due_at(start + Duration::seconds(30));
+        assert_eq!(due.len(), 1);
+        assert!(due.contains(&cron("0 * * * * *")));
+    }
+
+    #[test]
+    fn test_schedule_follows_daylight_saving_time() {
+        // Europe/Berlin switches from CET (+01:00) to CEST (+02:00) on the 29th of March 2026.
+        let berlin = Tz::Europe__Berlin;
+        let start = berlin.with_ymd_and_hms(2026, 3, 28, 12, 0, 0).unwrap().with_timezone(&Utc);
+        let mut scheduler = Scheduler::new(berlin);
+        scheduler.add_at(&cron("0 0 3 * * *"), start).unwrap();
+
+        // 03:00 local time before the switch is 02:00 UTC, after the switch it is 01:00 UTC.
+        assert!(scheduler.due_at(utc("2026-03-29T00:59:59Z")).is_empty());
+        assert_eq!(scheduler.due_at(utc("2026-03-29T01:00:00Z")).len(), 1);
+        assert!(scheduler.due_at(utc("2026-03-30T00:59:59Z")).is_empty());
+        assert_eq!(scheduler.due_at(utc("2026-03-30T01:00:00Z")).len(), 1);
+    }
+
+    #[test]
+    fn test_interval_fires_after_every_interval() {
+        let start = utc("2026-01-01T00:00:00Z");
+        let interval = Schedule::Interval {
+            every: std::time::Duration::from_secs(300),
+            splay: std::time::Duration::ZERO,
+        };
+        let mut scheduler = Scheduler::new(Tz::UTC);
+        scheduler.add_at(&interval, start).unwrap();
+
+        assert!(scheduler.due_at(start + Duration::seconds(299)).is_empty());
+        assert!(scheduler.due_at(start + Duration::seconds(300)).contains(&interval));
+        assert!(scheduler.due_at(start + Duration::seconds(599)).is_empty());
+        assert!(scheduler.due_at(start + Duration::seconds(600)).contains(&interval));
+    }
+
+    #[test]
+    fn test_interval_splay_delays_the_run() {
+        let start = utc("2026-01-01T00:00:00Z");
+        let interval = Schedule::Interval {
+            every: std::time::Duration::from_secs(300),
+            splay: std::time::Duration::from_secs(60),
+        };
+        let mut scheduler = Scheduler::new(Tz::UTC);
+        scheduler.add_at(&interval, start).unwrap();
+
+        assert!(scheduler.due_at(start + Duration::seconds(299)).is_empty());
+        assert!(scheduler.due_at(start + Duration::seconds(360)).contains(&interval));
+    }
+
+    #[test]
+    fn test_invalid_schedule_is_rejected() {
+        let mut scheduler = Scheduler::new(Tz::UTC);
+
+        assert!(scheduler.add(&cron("Not a valid cron expression")).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use signal_hook::consts::TERM_SIGNALS;
+use signal_hook::flag;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+use tracing::{error, info};
+
+/**
+How long a running sync may take to finish after a shutdown was requested.
+It is below the 10 second grace period of `docker stop`, so we never get killed halfway through a write of the state.
+*/
+pub(crate) const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(8);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/**
+A flag that is set once the process was asked to terminate (SIGTERM, SIGINT, ...).
+Long running work checks it between records and stops early.
+*/
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Shutdown {
+    requested: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+    /**
+    Sets the flag on the first termination signal. A second signal exits immediately, in case the shutdown got stuck.
+    */
+    pub(crate) fn listen(&self) -> Result<(), std::io::Error> {
+        for signal in TERM_SIGNALS {
+            // The conditional shutdown has to be registered first, so it only fires once the flag is already set.
+            flag::register_conditional_shutdown(*signal, 1, self.requested.clone())?;
+            flag::register(*signal, self.requested.clone())?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    /**
+    Starts a thread that exits the process if it is still running [`SHUTDOWN_TIMEOUT`] after a shutdown was requested.
+    */
+    pub(crate) fn spawn_watchdog(&self) {
+        let shutdown = self.clone();
+        std::thread::spawn(move || {
+            while !shutdown.is_requested() {
+                sleep(POLL_INTERVAL);
+            }
+
+            info!("Shutdown requested. Waiting up to {:?} for the running sync to finish...", SHUTDOWN_TIMEOUT);
+            sleep(SHUTDOWN_TIMEOUT);
+            error!("The running sync did not finish within {:?}. Exiting anyway.", SHUTDOWN_TIMEOUT);
+            std::process::exit(1);
+        });
+    }
+
+    /**
+    Sleeps for the given duration, but wakes up early when a shutdown is requested.
+    */
+    pub(crate) fn sleep(&self, duration: Duration) {
+        let mut remaining = duration;
+        while !remaining.is_zero() && !self.is_requested() {
+            let step = remaining.min(POLL_INTERVAL);
+            sleep(step);
+            remaining -= step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shutdown::Shutdown;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_shutdown_is_shared_between_clones() {
+        let shutdown = Shutdown::default();
+        let clone = shutdown.clone();
+
+        assert!(!clone.is_requested());
+        shutdown.request();
+        assert!(clone.is_requested());
+    }
+
+    #[test]
+    fn test_sleep_returns_early_when_shutdown_is_requested() {
+        let shutdown = Shutdown::default();
+        shutdown.request();
+
+        let start = Instant::now();
+        shutdown.sleep(Duration::from_secs(10));
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
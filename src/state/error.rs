@@ -0,0 +1,11 @@
+use std::io::Error as IoError;
+use thiserror::Error;
+use toml::ser::Error as SerializationError;
+
+#[derive(Debug, Error)]
+pub(crate) enum StateError {
+    #[error("I/O error occurred while writing the state file: {0}")]
+    Io(#[from] IoError),
+    #[error("TOML serialization error: {0}")]
+    Serialization(#[from] SerializationError),
+}
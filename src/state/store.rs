@@ -0,0 +1,261 @@
+use crate::configuration::user::records::DnsType;
+use crate::dns::record::CachedRecord;
+use crate::state::error::StateError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SyncState {
+    #[serde(default)]
+    pub(crate) records: BTreeMap<String, RecordState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RecordState {
+    /**
+    All records we sync for a name and type. There is more than one when duplicates are kept on purpose.
+    */
+    pub(crate) records: Vec<CachedRecord>,
+    pub(crate) synced_at: DateTime<Utc>,
+    /**
+    The last time dyncloud created or updated the records. Checks that left them alone don't count.
+    */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) written_at: Option<DateTime<Utc>>,
+}
+
+/**
+Keeps the sync state of all records and optionally persists it to a file, so restarts don't have to start from scratch.
+*/
+pub(crate) struct StateStore {
+    path: Option<PathBuf>,
+    state: Mutex<SyncState>,
+}
+
+impl StateStore {
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            path: None,
+            state: Default::default(),
+        }
+    }
+
+    /**
+    Loads the state from the given file.
+    A missing or unreadable file is not an error, we just start with an empty state and overwrite it on the next write.
+    */
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let state = match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+                warn!("Ignoring corrupt state file {}: {}", path.display(), err);
+                SyncState::default()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                debug!("No state file found at {}. Starting with an empty state.", path.display());
+                SyncState::default()
+            }
+            Err(err) => {
+                warn!("Could not read state file {}: {}", path.display(), err);
+                SyncState::default()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            state: Mutex::new(state),
+        }
+    }
+
+    pub(crate) fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<RecordState> {
+        #[allow(clippy::expect_used)]
+        let state = self.state.lock().expect("State store poisoned");
+        state.records.get(key).cloned()
+    }
+
+    pub(crate) fn set(&self, key: String, records: Vec<CachedRecord>) {
+        #[allow(clippy::expect_used)]
+        let mut state = self.state.lock().expect("State store poisoned");
+        let written_at = state.records.get(&key).and_then(|record| record.written_at);
+        state.records.insert(
+            key,
+            RecordState {
+                records,
+                synced_at: Utc::now(),
+                written_at,
+            },
+        );
+    }
+
+    /**
+    Like [`StateStore::set`], but also remembers when the records were written to the provider.
+    */
+    pub(crate) fn set_written(&self, key: String, records: Vec<CachedRecord>, written_at: DateTime<Utc>) {
+        #[allow(clippy::expect_used)]
+        let mut state = self.state.lock().expect("State store poisoned");
+        state.records.insert(
+            key,
+            RecordState {
+                records,
+                synced_at: Utc::now(),
+                written_at: Some(written_at),
+            },
+        );
+    }
+
+    pub(crate) fn remove(&self, key: &str) {
+        #[allow(clippy::expect_used)]
+        let mut state = self.state.lock().expect("State store poisoned");
+        state.records.remove(key);
+    }
+
+    /**
+    Drops all entries that don't belong to one of the given keys, e.g. records that were removed from the config.
+    */
+    pub(crate) fn retain(&self, keys: &HashSet<String>) {
+        #[allow(clippy::expect_used)]
+        let mut state = self.state.lock().expect("State store poisoned");
+        state.records.retain(|key, _| keys.contains(key));
+    }
+
+    /**
+    Writes the state to the state file, if there is one.
+    The state is written to a temporary file first and then moved into place,
+    so a crash never leaves a half written file behind.
+    */
+    pub(crate) fn persist(&self) -> Result<(), StateError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let content = {
+            #[allow(clippy::expect_used)]
+            let state = self.state.lock().expect("State store poisoned");
+            toml::to_string(&*state)?
+        };
+
+        write_atomically(path, content.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+pub(crate) fn cloudflare_state_key(zone_id: &str, name: &str, dns_type: &DnsType) -> String {
+    format!("cloudflare/{}/{}/{}", zone_id, name, dns_type)
+}
+
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), std::io::Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+
+    std::fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::configuration::user::records::DnsType;
+    use crate::dns::record::CachedRecord;
+    use crate::state::store::{StateStore, cloudflare_state_key};
+    use chrono::Utc;
+    use cloudflare::endpoints::dns::dns::DnsContent;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    fn create_test_record() -> CachedRecord {
+        CachedRecord {
+            id: "2eef68ee36ba268bb9aa3593e3ff7dc3".to_string(),
+            content: DnsContent::A {
+                content: "127.0.0.1".parse().unwrap(),
+            },
+            ttl: 60,
+            proxied: false,
+            comment: None,
+        }
+    }
+
+    fn test_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dyncloud-{}-{}.toml", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        path
+    }
+
+    #[test]
+    fn test_missing_file_results_in_empty_state() {
+        let store = StateStore::load(test_file("missing"));
+
+        assert!(store.get("some key").is_none());
+    }
+
+    #[test]
+    fn test_persist_and_load() {
+        let path = test_file("roundtrip");
+        let key = cloudflare_state_key("ZoneID", "test.example.internal", &DnsType::A);
+
+        let store = StateStore::load(path.clone());
+        store.set(key.clone(), vec![create_test_record()]);
+        store.persist().unwrap();
+
+        let loaded = StateStore::load(path.clone()).get(&key).unwrap();
+        assert_eq!(loaded.records.len(), 1);
+        assert_eq!(loaded.records[0].id, "2eef68ee36ba268bb9aa3593e3ff7dc3");
+        assert_eq!(loaded.records[0].ttl, 60);
+        assert!(matches!(
+            loaded.records[0].content,
+            DnsContent::A { content } if content.to_string() == "127.0.0.1"
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_file_results_in_empty_state() {
+        let path = test_file("corrupt");
+        std::fs::write(&path, "this is { not valid toml").unwrap();
+
+        let store = StateStore::load(path.clone());
+        assert!(store.get("some key").is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_set_keeps_the_last_write() {
+        let store = StateStore::in_memory();
+        let written_at = Utc::now() - chrono::Duration::days(3);
+
+        store.set("key".to_string(), vec![create_test_record()]);
+        assert_eq!(store.get("key").unwrap().written_at, None);
+
+        store.set_written("key".to_string(), vec![create_test_record()], written_at);
+        store.set("key".to_string(), vec![create_test_record()]);
+        assert_eq!(store.get("key").unwrap().written_at, Some(written_at));
+    }
+
+    #[test]
+    fn test_retain_drops_unknown_records() {
+        let store = StateStore::in_memory();
+        store.set("known".to_string(), vec![create_test_record()]);
+        store.set("removed".to_string(), vec![create_test_record()]);
+
+        store.retain(&HashSet::from(["known".to_string()]));
+
+        assert!(store.get("known").is_some());
+        assert!(store.get("removed").is_none());
+    }
+}